@@ -0,0 +1,265 @@
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs,
+};
+
+use anyhow::{Context, anyhow, bail};
+use serde::Deserialize;
+
+use crate::{
+    cli::{Config, parse_view_options},
+    pipeline::{build_graph, load_metadata, write_graph},
+};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchSpec {
+    /// options that apply to all views, in front of their own options
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default, rename = "view")]
+    views: Vec<View>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct View {
+    name: String,
+    /// command-line options like `--root` or `--all-deps`
+    #[serde(default)]
+    args: Vec<String>,
+    /// where to write the graph, not needed for output formats that name their own files
+    output: Option<String>,
+}
+
+/// The options that determine which metadata is read, so it is only read once for all views that
+/// share them.
+#[derive(PartialEq, Eq, Hash)]
+struct MetadataOptions<'a> {
+    features: &'a [String],
+    all_features: bool,
+    no_default_features: bool,
+    filter_platform: &'a [String],
+    manifest_path: &'a [String],
+    frozen: bool,
+    locked: bool,
+    offline: bool,
+    unstable_flags: &'a [String],
+    from_sbom: Option<&'a str>,
+    from_binary: Option<&'a str>,
+    lockfile: Option<&'a str>,
+}
+
+impl<'a> MetadataOptions<'a> {
+    fn new(config: &'a Config) -> Self {
+        Self {
+            features: &config.features,
+            all_features: config.all_features,
+            no_default_features: config.no_default_features,
+            filter_platform: &config.filter_platform,
+            manifest_path: &config.manifest_path,
+            frozen: config.frozen,
+            locked: config.locked,
+            offline: config.offline,
+            unstable_flags: &config.unstable_flags,
+            from_sbom: config.from_sbom.as_deref(),
+            from_binary: config.from_binary.as_deref(),
+            lockfile: config.lockfile.as_deref(),
+        }
+    }
+}
+
+/// Renders all views listed in the batch file at `path`.
+///
+/// All options are checked before anything is rendered, so a typo in the last view doesn't leave
+/// the outputs of the others half-updated.
+pub(crate) fn run_batch(path: &str) -> anyhow::Result<()> {
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read `{path}`"))?;
+    let views = parse_batch(&text, path)?;
+
+    let mut metadata_cache = HashMap::new();
+    for (view, config) in &views {
+        let (metadata, workspaces) = match metadata_cache.entry(MetadataOptions::new(config)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(load_metadata(config)?),
+        };
+
+        let graph = build_graph(metadata.clone(), workspaces, config)
+            .with_context(|| format!("failed to build the graph for view `{}`", view.name))?;
+        write_graph(&graph, config, view.output.as_deref())
+            .with_context(|| format!("failed to write view `{}`", view.name))?;
+    }
+
+    Ok(())
+}
+
+/// Parses the batch file and the options of all views in it.
+fn parse_batch(text: &str, path: &str) -> anyhow::Result<Vec<(View, Config)>> {
+    let spec: BatchSpec =
+        toml::from_str(text).with_context(|| format!("failed to parse `{path}`"))?;
+    if spec.views.is_empty() {
+        bail!("`{path}` doesn't contain any views");
+    }
+
+    let mut views = Vec::new();
+    for view in spec.views {
+        let args: Vec<_> = spec.args.iter().chain(&view.args).cloned().collect();
+        let mut config = parse_view_options(&args).map_err(|e| {
+            // Only the first line is relevant, the rest is about the command line
+            let message = e.to_string();
+            let message = message.lines().next().unwrap_or_default();
+            let message = message.trim_start_matches("error: ");
+            anyhow!("invalid options for view `{}`: {message}", view.name)
+        })?;
+
+        if config.batch.is_some() {
+            bail!("view `{}` can't use `--batch`", view.name);
+        }
+        let writes_own_files = config.sqlite.is_some()
+            || config.svg.is_some()
+            || matches!(config.output_format.as_str(), "csv" | "tsv");
+        match (&view.output, writes_own_files) {
+            (None, false) => bail!("view `{}` has no `output` path", view.name),
+            (Some(_), true) => bail!(
+                "view `{}` can't have an `output` path, its output format writes files of its own",
+                view.name
+            ),
+            _ => {}
+        }
+        // Colors are only detected for stdout, but views are written to files
+        if config.color == "auto" {
+            config.color = "never".to_owned();
+        }
+
+        views.push((view, config));
+    }
+
+    Ok(views)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf, process};
+
+    use super::*;
+
+    fn error(text: &str) -> String {
+        format!("{:#}", parse_batch(text, "batch.toml").err().unwrap())
+    }
+
+    #[test]
+    fn shared_args_apply_to_all_views() {
+        let views = parse_batch(
+            r#"
+            args = ["--all-deps"]
+
+            [[view]]
+            name = "full"
+            args = ["--theme", "dark"]
+            output = "full.dot"
+
+            [[view]]
+            name = "monochrome"
+            args = ["--theme", "monochrome", "--color", "always"]
+            output = "monochrome.txt"
+
+            [[view]]
+            name = "tables"
+            args = ["--output-format", "csv"]
+            "#,
+            "batch.toml",
+        )
+        .unwrap();
+
+        let names: Vec<_> = views.iter().map(|(view, _)| view.name.as_str()).collect();
+        assert_eq!(names, ["full", "monochrome", "tables"]);
+        assert!(views.iter().all(|(_, config)| config.dev_deps && config.build_deps));
+        assert_eq!(views[0].1.theme, "dark");
+        assert_eq!(views[1].1.theme, "monochrome");
+        assert_eq!(views[0].1.color, "never");
+        assert_eq!(views[1].1.color, "always");
+        assert_eq!(views[2].0.output, None);
+    }
+
+    #[test]
+    fn unknown_fields_are_rejected() {
+        let err = error("[[view]]\nname = \"a\"\noutptu = \"a.dot\"\n");
+        assert!(err.contains("unknown field `outptu`"), "{err}");
+        let err = error("argz = []\n[[view]]\nname = \"a\"\noutput = \"a.dot\"\n");
+        assert!(err.contains("unknown field `argz`"), "{err}");
+    }
+
+    #[test]
+    fn invalid_views_are_rejected() {
+        assert_eq!(error(""), "`batch.toml` doesn't contain any views");
+        assert_eq!(error("[[view]]\nname = \"a\"\n"), "view `a` has no `output` path");
+        assert_eq!(
+            error("[[view]]\nname = \"a\"\nargs = [\"--svg\", \"a.svg\"]\noutput = \"a.dot\"\n"),
+            "view `a` can't have an `output` path, its output format writes files of its own",
+        );
+        assert_eq!(
+            error(
+                "[[view]]\nname = \"a\"\nargs = [\"--output-format\", \"tsv\"]\noutput = \"a\"\n"
+            ),
+            "view `a` can't have an `output` path, its output format writes files of its own",
+        );
+        assert!(
+            error("[[view]]\nname = \"a\"\nargs = [\"--bogus\"]\noutput = \"a.dot\"\n")
+                .starts_with("invalid options for view `a`: unexpected argument '--bogus'")
+        );
+    }
+
+    /// A directory for the outputs of a batch, removed at the end of the test.
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("depgraph-batch-{name}-{}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// Writes a batch file with one view reading this repository's Cargo.lock, and a second
+        /// one with the given options.
+        fn write_batch(&self, second_args: &str) -> String {
+            let lockfile = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.lock");
+            let text = format!(
+                "args = [\"--lockfile\", {lockfile:?}]\n\
+                 [[view]]\nname = \"first\"\noutput = {:?}\n\
+                 [[view]]\nname = \"second\"\nargs = [{second_args}]\noutput = {:?}\n",
+                self.0.join("first.dot"),
+                self.0.join("second.txt"),
+            );
+            let path = self.0.join("batch.toml");
+            fs::write(&path, text).unwrap();
+            path.to_str().unwrap().to_owned()
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn all_views_are_written() {
+        let dir = TestDir::new("written");
+        run_batch(&dir.write_batch("\"--output-format\", \"tree\"")).unwrap();
+
+        let first = fs::read_to_string(dir.0.join("first.dot")).unwrap();
+        assert!(first.starts_with("digraph {"));
+        let second = fs::read_to_string(dir.0.join("second.txt")).unwrap();
+        assert!(second.starts_with("cargo-depgraph v"));
+    }
+
+    #[test]
+    fn nothing_is_written_if_a_view_is_invalid() {
+        let dir = TestDir::new("invalid");
+        let err = run_batch(&dir.write_batch("\"--output-format\", \"bogus\"")).unwrap_err();
+        assert!(err.to_string().starts_with("invalid options for view `second`"), "{err}");
+        assert!(!dir.0.join("first.dot").exists());
+        assert!(!dir.0.join("second.txt").exists());
+    }
+}
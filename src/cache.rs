@@ -0,0 +1,307 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    env,
+    ffi::OsString,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::Context;
+use cargo_metadata::Metadata;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Config;
+
+/// A cached `cargo metadata` result, along with what it depends on.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    /// a hash of the cargo version and of the cargo configuration files
+    environment_hash: u64,
+    lockfile_hash: u64,
+    /// the manifests of all packages from local paths (including the workspace members) and of
+    /// the workspace root, with the hashes of their contents
+    manifests: Vec<(PathBuf, u64)>,
+    metadata: Metadata,
+}
+
+/// Returns the `cargo metadata` result for the given manifest from the cache in the target
+/// directory if Cargo.lock, all manifests of the workspace, the cargo version and the cargo
+/// configuration are unchanged since it was stored, otherwise calls `run` and stores its result.
+///
+/// Nothing is cached for workspaces without a Cargo.lock.
+pub(crate) fn cached_metadata(
+    config: &Config,
+    manifest_path: Option<&String>,
+    run: impl FnOnce() -> anyhow::Result<Metadata>,
+) -> anyhow::Result<Metadata> {
+    let start = match manifest_path {
+        Some(path) => fs::canonicalize(path)
+            .with_context(|| format!("failed to find `{path}`"))?
+            .parent()
+            .map(Path::to_owned),
+        None => env::current_dir().ok(),
+    };
+    let Some(root) = start.as_deref().and_then(find_workspace_root) else {
+        return run();
+    };
+    let Some(environment_hash) = environment_hash(&root) else {
+        return run();
+    };
+
+    let target_dir = match env::var_os("CARGO_TARGET_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => root.join("target"),
+    };
+    let cache_path = target_dir
+        .join("depgraph-cache")
+        .join(format!("metadata-{:016x}.json", options_hash(config, manifest_path)));
+    let lockfile_hash = file_hash(&root.join("Cargo.lock"));
+
+    if let Ok(json) = fs::read(&cache_path)
+        && let Ok(entry) = serde_json::from_slice::<CacheEntry>(&json)
+        && entry.environment_hash == environment_hash
+        && Some(entry.lockfile_hash) == lockfile_hash
+        && entry.manifests.iter().all(|(path, hash)| file_hash(path) == Some(*hash))
+    {
+        return Ok(entry.metadata);
+    }
+
+    let metadata = run()?;
+    if let Some(lockfile_hash) = lockfile_hash
+        && let Err(e) = store(&cache_path, environment_hash, lockfile_hash, &metadata)
+    {
+        eprintln!("warning: failed to cache the metadata: {e:#}");
+    }
+
+    Ok(metadata)
+}
+
+fn store(
+    cache_path: &Path,
+    environment_hash: u64,
+    lockfile_hash: u64,
+    metadata: &Metadata,
+) -> anyhow::Result<()> {
+    let manifests = metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.source.is_none())
+        .map(|pkg| pkg.manifest_path.as_std_path())
+        .chain([metadata.workspace_root.join("Cargo.toml").as_std_path()])
+        .filter_map(|path| Some((path.to_owned(), file_hash(path)?)))
+        .collect();
+    let entry =
+        CacheEntry { environment_hash, lockfile_hash, manifests, metadata: metadata.clone() };
+
+    if let Some(dir) = cache_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(cache_path, serde_json::to_vec(&entry)?)?;
+
+    Ok(())
+}
+
+/// The closest directory containing a Cargo.lock, starting at `dir`.
+fn find_workspace_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors().find(|dir| dir.join("Cargo.lock").is_file()).map(Path::to_owned)
+}
+
+/// A hash of the options that influence the `cargo metadata` output, so different combinations of
+/// features and platforms are cached separately.
+fn options_hash(config: &Config, manifest_path: Option<&String>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    manifest_path.hash(&mut hasher);
+    config.features.hash(&mut hasher);
+    config.all_features.hash(&mut hasher);
+    config.no_default_features.hash(&mut hasher);
+    config.filter_platform.hash(&mut hasher);
+    config.offline.hash(&mut hasher);
+    config.unstable_flags.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A hash of the other things that influence the `cargo metadata` output: the version of cargo
+/// and the configuration files it reads, both from the directories above the current one and the
+/// workspace root and from the cargo home directory.
+///
+/// Returns `None` if the version of cargo can't be determined, so nothing is cached.
+fn environment_hash(root: &Path) -> Option<u64> {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let output = Command::new(cargo).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let mut hasher = DefaultHasher::new();
+    output.stdout.hash(&mut hasher);
+
+    let cargo_home = env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::home_dir().map(|home| home.join(".cargo")));
+    let current_dir = env::current_dir().ok();
+    let mut config_dirs: Vec<_> = current_dir
+        .iter()
+        .flat_map(|dir| dir.ancestors())
+        .chain(root.ancestors())
+        .map(|dir| dir.join(".cargo"))
+        .chain(cargo_home)
+        .collect();
+    config_dirs.sort();
+    config_dirs.dedup();
+
+    for dir in config_dirs {
+        for name in ["config", "config.toml"] {
+            let path = dir.join(name);
+            if let Some(hash) = file_hash(&path) {
+                (path, hash).hash(&mut hasher);
+            }
+        }
+    }
+
+    Some(hasher.finish())
+}
+
+fn file_hash(path: &Path) -> Option<u64> {
+    let contents = fs::read(path).ok()?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, process};
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::cli::parse_view_options;
+
+    /// A workspace with a single package in a new temporary directory.
+    struct TestWorkspace {
+        dir: PathBuf,
+        manifest_path: String,
+    }
+
+    impl TestWorkspace {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!("depgraph-cache-{name}-{}", process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("Cargo.toml"), "[package]\nname = \"app\"\n").unwrap();
+            fs::write(dir.join("Cargo.lock"), "version = 4\n").unwrap();
+            let manifest_path = dir.join("Cargo.toml").to_str().unwrap().to_owned();
+
+            Self { dir, manifest_path }
+        }
+
+        fn metadata(&self) -> Metadata {
+            let dir = fs::canonicalize(&self.dir).unwrap();
+            let id = format!("path+file://{}#app@0.1.0", dir.display());
+            serde_json::from_value(json!({
+                "packages": [{
+                    "name": "app",
+                    "version": "0.1.0",
+                    "id": id,
+                    "source": null,
+                    "dependencies": [],
+                    "targets": [],
+                    "features": {},
+                    "manifest_path": dir.join("Cargo.toml"),
+                }],
+                "workspace_members": [id],
+                "resolve": null,
+                "workspace_root": dir,
+                "target_directory": dir.join("target"),
+                "version": 1,
+            }))
+            .unwrap()
+        }
+
+        /// Gets the metadata through the cache, returning whether `cargo metadata` would have run.
+        fn load(&self, args: &[&str]) -> bool {
+            let args: Vec<_> = args.iter().map(|&arg| arg.to_owned()).collect();
+            let config = parse_view_options(&args).unwrap();
+            let ran = Cell::new(false);
+            cached_metadata(&config, Some(&self.manifest_path), || {
+                ran.set(true);
+                Ok(self.metadata())
+            })
+            .unwrap();
+
+            ran.get()
+        }
+    }
+
+    impl Drop for TestWorkspace {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn unchanged_workspace_is_a_hit() {
+        let workspace = TestWorkspace::new("hit");
+        assert!(workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache"]));
+    }
+
+    #[test]
+    fn different_options_are_a_miss() {
+        let workspace = TestWorkspace::new("options");
+        assert!(workspace.load(&["--cache"]));
+        assert!(workspace.load(&["--cache", "--all-features"]));
+        assert!(workspace.load(&["--cache", "--offline"]));
+        assert!(!workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache", "--offline"]));
+    }
+
+    #[test]
+    fn changed_manifest_invalidates_the_cache() {
+        let workspace = TestWorkspace::new("manifest");
+        assert!(workspace.load(&["--cache"]));
+        fs::write(&workspace.manifest_path, "[package]\nname = \"app\"\nversion = \"0.2.0\"\n")
+            .unwrap();
+        assert!(workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache"]));
+    }
+
+    #[test]
+    fn changed_lockfile_invalidates_the_cache() {
+        let workspace = TestWorkspace::new("lockfile");
+        assert!(workspace.load(&["--cache"]));
+        fs::write(workspace.dir.join("Cargo.lock"), "version = 3\n").unwrap();
+        assert!(workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache"]));
+    }
+
+    #[test]
+    fn changed_cargo_config_invalidates_the_cache() {
+        let workspace = TestWorkspace::new("config");
+        assert!(workspace.load(&["--cache"]));
+        fs::create_dir(workspace.dir.join(".cargo")).unwrap();
+        fs::write(workspace.dir.join(".cargo/config.toml"), "[net]\noffline = true\n").unwrap();
+        assert!(workspace.load(&["--cache"]));
+        assert!(!workspace.load(&["--cache"]));
+    }
+
+    #[test]
+    fn nothing_is_cached_without_lockfile() {
+        let workspace = TestWorkspace::new("no-lockfile");
+        fs::remove_file(workspace.dir.join("Cargo.lock")).unwrap();
+        assert!(workspace.load(&["--cache"]));
+        assert!(workspace.load(&["--cache"]));
+    }
+
+    #[test]
+    fn cache_conflicts_with_lock_checks() {
+        for flag in ["--locked", "--frozen"] {
+            let args = ["--cache".to_owned(), flag.to_owned()];
+            assert!(parse_view_options(&args).is_err());
+        }
+    }
+}
@@ -1,236 +1,627 @@
-use clap::{Arg, ArgAction, Command, value_parser};
+use clap::{Arg, ArgAction, ArgMatches, Command, builder::RangedU64ValueParser, value_parser};
+
+use crate::{output::BUILTIN_THEMES, package::PackageSource};
 
 pub(crate) struct Config {
     pub build_deps: bool,
     pub dev_deps: bool,
     pub target_deps: bool,
     pub dedup_transitive_deps: bool,
+    pub merge_edges: bool,
     pub hide: Vec<String>,
+    pub prune: Vec<String>,
     pub exclude: Vec<String>,
     pub include: Vec<String>,
+    pub source_filter: Vec<String>,
+    pub exclude_source: Vec<String>,
     pub root: Vec<String>,
     pub workspace_only: bool,
     pub focus: Vec<String>,
     pub depth: Option<u32>,
+    pub path_from: Vec<String>,
+    pub path_to: Vec<String>,
+    pub max_paths: Option<usize>,
+    pub highlight_paths: bool,
+    pub output_format: String,
+    pub svg: Option<String>,
+    pub output_dir: String,
+    pub sqlite: Option<String>,
+    pub project: Option<String>,
+    pub legend: bool,
+    pub cluster_workspaces: bool,
+    pub edge_labels: bool,
+    pub rank_by: Option<String>,
+    pub rankdir: Option<String>,
+    pub svg_attrs: bool,
+    pub link_to: String,
+    pub theme: String,
+    pub theme_file: Option<String>,
+    pub color: String,
+    pub max_width: Option<usize>,
 
     pub features: Vec<String>,
     pub all_features: bool,
     pub no_default_features: bool,
     pub filter_platform: Vec<String>,
-    pub manifest_path: Option<String>,
+    pub manifest_path: Vec<String>,
     pub frozen: bool,
     pub locked: bool,
     pub offline: bool,
     pub unstable_flags: Vec<String>,
+    pub cache: bool,
+
+    pub from_sbom: Option<String>,
+    pub from_binary: Option<String>,
+    pub lockfile: Option<String>,
+
+    pub batch: Option<String>,
 }
 
+/// Options that are passed through to `cargo metadata`, which don't apply to other inputs.
+const CARGO_METADATA_ARGS: [&str; 10] = [
+    "features",
+    "all_features",
+    "no_default_features",
+    "filter_platform",
+    "manifest_path",
+    "frozen",
+    "locked",
+    "offline",
+    "unstable_flags",
+    "cache",
+];
+
 pub(crate) fn parse_options() -> Config {
-    let matches = Command::new("cargo-depgraph")
-        .bin_name("cargo")
-        .version(env!("CARGO_PKG_VERSION"))
-        .subcommand(
-            Command::new("depgraph")
-                .arg(Arg::new("all_deps").long("all-deps").action(ArgAction::SetTrue).help(
-                    "Include all dependencies in the graph \
+    let matches = command().get_matches();
+    config_from_matches(matches.subcommand_matches("depgraph").unwrap())
+}
+
+/// Parses the options of a view in a batch file, given as command-line arguments.
+pub(crate) fn parse_view_options(args: &[String]) -> Result<Config, clap::Error> {
+    let args = ["cargo", "depgraph"].into_iter().map(ToOwned::to_owned).chain(args.iter().cloned());
+    let matches = command().try_get_matches_from(args)?;
+    Ok(config_from_matches(matches.subcommand_matches("depgraph").unwrap()))
+}
+
+fn command() -> Command {
+    Command::new("cargo-depgraph").bin_name("cargo").version(env!("CARGO_PKG_VERSION")).subcommand(
+        Command::new("depgraph")
+            .arg(Arg::new("all_deps").long("all-deps").action(ArgAction::SetTrue).help(
+                "Include all dependencies in the graph \
                      (shorthand for --build-deps --dev-deps --target-deps)",
-                ))
-                .arg(
-                    Arg::new("build_deps")
-                        .long("build-deps")
-                        .action(ArgAction::SetTrue)
-                        .help("Include build-dependencies in the graph"),
-                )
-                .arg(
-                    Arg::new("dev_deps")
-                        .long("dev-deps")
-                        .action(ArgAction::SetTrue)
-                        .help("Include dev-dependencies in the graph"),
-                )
-                .arg(
-                    Arg::new("target_deps")
-                        .long("target-deps")
-                        .action(ArgAction::SetTrue)
-                        .help("Include cfg() dependencies in the graph"),
-                )
-                .arg(
-                    Arg::new("dedup_transitive_deps")
-                        .long("dedup-transitive-deps")
-                        .action(ArgAction::SetTrue)
-                        .help(
-                            "Remove direct dependency edges where there's at \
+            ))
+            .arg(
+                Arg::new("build_deps")
+                    .long("build-deps")
+                    .action(ArgAction::SetTrue)
+                    .help("Include build-dependencies in the graph"),
+            )
+            .arg(
+                Arg::new("dev_deps")
+                    .long("dev-deps")
+                    .action(ArgAction::SetTrue)
+                    .help("Include dev-dependencies in the graph"),
+            )
+            .arg(
+                Arg::new("target_deps")
+                    .long("target-deps")
+                    .action(ArgAction::SetTrue)
+                    .help("Include cfg() dependencies in the graph"),
+            )
+            .arg(
+                Arg::new("dedup_transitive_deps")
+                    .long("dedup-transitive-deps")
+                    .action(ArgAction::SetTrue)
+                    .help(
+                        "Remove direct dependency edges where there's at \
                              least one transitive dependency of the same kind.",
-                        ),
-                )
-                .arg(
-                    Arg::new("hide")
-                        .long("hide")
-                        .action(ArgAction::Append)
-                        .value_delimiter(',')
-                        .help(
-                            "Package name(s) to hide; can be given as a comma-separated list or \
+                    ),
+            )
+            .arg(Arg::new("merge_edges").long("merge-edges").action(ArgAction::SetTrue).help(
+                "Merge multiple dependency edges between the same two packages (e.g. \
+                             a normal and a dev-dependency) into one, labeled with the kinds of \
+                             the merged edges",
+            ))
+            .arg(Arg::new("hide").long("hide").action(ArgAction::Append).value_delimiter(',').help(
+                "Package name(s) to hide; can be given as a comma-separated list or \
                              as multiple arguments\n\n\
                              In contrast to --exclude, hidden packages will still contribute in \
                              dependency kind resolution",
-                        ),
-                )
-                .arg(
-                    Arg::new("exclude")
-                        .long("exclude")
-                        .action(ArgAction::Append)
-                        .value_delimiter(',')
-                        .help(
-                            "Package name(s) to ignore; can be given as a comma-separated list or \
+            ))
+            .arg(
+                Arg::new("prune")
+                    .long("prune")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .help(
+                        "Package name(s) to prune; can be given as a comma-separated list or \
+                             as multiple arguments\n\n\
+                             In contrast to --hide, pruned packages are kept, but all of their \
+                             dependencies that aren't also used by other packages are replaced by \
+                             a single placeholder node",
+                    ),
+            )
+            .arg(
+                Arg::new("exclude")
+                    .long("exclude")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .help(
+                        "Package name(s) to ignore; can be given as a comma-separated list or \
                              as multiple arguments\n\n\
                              In contrast to --hide, excluded packages will not contribute in \
                              dependency kind resolution",
-                        ),
-                )
-                .arg(
-                    Arg::new("include")
-                        .long("include")
-                        .action(ArgAction::Append)
-                        .value_delimiter(',')
-                        .help(
-                            "Package name(s) to include; can be given as a comma-separated list or \
+                    ),
+            )
+            .arg(
+                Arg::new("include")
+                    .long("include")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .help(
+                        "Package name(s) to include; can be given as a comma-separated list or \
                              as multiple arguments\n\n\
                              Only included packages will be shown",
-                        ),
-                )
-                .arg(
-                    Arg::new("root")
-                        .long("root")
-                        .action(ArgAction::Append)
-                        .value_delimiter(',')
-                        .help("Workspace package(s) to list dependencies for. Default: all"),
-                )
-                .arg(
-                    Arg::new("workspace_only")
-                        .long("workspace-only")
-                        .action(ArgAction::SetTrue)
-                        .help("Exclude all packages outside of the workspace"),
-                )
-                .arg(
-                    Arg::new("focus")
-                        .long("focus")
-                        .action(ArgAction::Append)
-                        .value_delimiter(',')
-                        .help(
-                            "Package name(s) to focus on: only the given packages, the workspace \
+                    ),
+            )
+            .arg(
+                Arg::new("source_filter")
+                    .long("source-filter")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .value_parser(PackageSource::SELECTORS)
+                    .value_name("SOURCES")
+                    .help(
+                        "Package source(s) to include; can be given as a comma-separated list \
+                             or as multiple arguments\n\n\
+                             Only dependencies from the given sources will be shown, workspace \
+                             members are always included",
+                    ),
+            )
+            .arg(
+                Arg::new("exclude_source")
+                    .long("exclude-source")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .value_parser(PackageSource::SELECTORS)
+                    .value_name("SOURCES")
+                    .help(
+                        "Package source(s) to ignore; can be given as a comma-separated list \
+                             or as multiple arguments\n\n\
+                             Workspace members are never excluded",
+                    ),
+            )
+            .arg(
+                Arg::new("root")
+                    .long("root")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .help("Workspace package(s) to list dependencies for. Default: all"),
+            )
+            .arg(
+                Arg::new("workspace_only")
+                    .long("workspace-only")
+                    .action(ArgAction::SetTrue)
+                    .help("Exclude all packages outside of the workspace"),
+            )
+            .arg(
+                Arg::new("focus")
+                    .long("focus")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .help(
+                        "Package name(s) to focus on: only the given packages, the workspace \
                              members that depend on them and any intermediate dependencies are \
                              going to be present in the output; can be given as a comma-separated \
                              list or as multiple arguments",
-                        ),
-                )
-                .arg(
-                    Arg::new("depth")
-                        .long("depth")
-                        .value_parser(value_parser!(u32))
-                        .action(ArgAction::Set)
-                        .help("Limit the depth of the dependency graph"),
-                )
-                // Options to pass through to `cargo metadata`
-                .arg(
-                    Arg::new("features")
-                        .long("features")
-                        .help("List of features to activate")
-                        .action(ArgAction::Append)
-                        .value_name("FEATURES"),
-                )
-                .arg(
-                    Arg::new("all_features")
-                        .long("all-features")
-                        .action(ArgAction::SetTrue)
-                        .help("Activate all available features"),
-                )
-                .arg(
-                    Arg::new("no_default_features")
-                        .long("no-default-features")
-                        .action(ArgAction::SetTrue)
-                        .help("Do not activate the `default` feature"),
-                )
-                .arg(
-                    Arg::new("filter_platform")
-                        .long("filter-platform")
-                        .help("Only include resolve dependencies matching the given target-triple")
-                        .action(ArgAction::Append)
-                        .number_of_values(1)
-                        .value_name("TRIPLE"),
-                )
-                .arg(
-                    Arg::new("manifest_path")
-                        .long("manifest-path")
-                        .help("Path to Cargo.toml")
-                        .value_name("PATH"),
-                )
-                .arg(
-                    Arg::new("frozen")
-                        .long("frozen")
-                        .action(ArgAction::SetTrue)
-                        .help("Require Cargo.lock and cache are up to date"),
-                )
-                .arg(
-                    Arg::new("locked")
-                        .long("locked")
-                        .action(ArgAction::SetTrue)
-                        .help("Require Cargo.lock is up to date"),
-                )
-                .arg(
-                    Arg::new("offline")
-                        .long("offline")
-                        .action(ArgAction::SetTrue)
-                        .help("Run without accessing the network"),
-                )
-                .arg(
-                    Arg::new("unstable_flags")
-                        .short('Z')
-                        .help(
-                            "Unstable (nightly-only) flags to Cargo, see \
+                    ),
+            )
+            .arg(
+                Arg::new("depth")
+                    .long("depth")
+                    .value_parser(value_parser!(u32))
+                    .action(ArgAction::Set)
+                    .help("Limit the depth of the dependency graph"),
+            )
+            .arg(
+                Arg::new("path_from")
+                    .long("path-from")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .requires("path_to")
+                    .help(
+                        "Package name(s) to start dependency paths from: only packages and \
+                             dependency edges on a path from one of these packages to one of the \
+                             --path-to packages are going to be present in the output; can be \
+                             given as a comma-separated list or as multiple arguments",
+                    ),
+            )
+            .arg(
+                Arg::new("path_to")
+                    .long("path-to")
+                    .action(ArgAction::Append)
+                    .value_delimiter(',')
+                    .requires("path_from")
+                    .help(
+                        "Package name(s) that dependency paths should end at; can be given as \
+                             a comma-separated list or as multiple arguments",
+                    ),
+            )
+            .arg(
+                Arg::new("max_paths")
+                    .long("max-paths")
+                    .value_parser(RangedU64ValueParser::<usize>::new().range(1..))
+                    .action(ArgAction::Set)
+                    .requires("path_from")
+                    .value_name("N")
+                    .help("Only consider the N shortest paths between --path-from and --path-to"),
+            )
+            .arg(
+                Arg::new("highlight_paths")
+                    .long("highlight-paths")
+                    .action(ArgAction::SetTrue)
+                    .requires("path_from")
+                    .help(
+                        "Highlight the paths between --path-from and --path-to instead of \
+                             removing everything else from the output",
+                    ),
+            )
+            .arg(
+                Arg::new("output_format")
+                    .long("output-format")
+                    .value_parser([
+                        "dot",
+                        "tree",
+                        "unicode",
+                        "ascii",
+                        "graphml",
+                        "gexf",
+                        "d2",
+                        "plantuml",
+                        "dsm-csv",
+                        "dsm-html",
+                        "csv",
+                        "tsv",
+                        "cypher",
+                        "cyclonedx",
+                        "spdx",
+                    ])
+                    .default_value("dot")
+                    .value_name("FORMAT")
+                    .help(
+                        "Output format: dot for rendering with Graphviz, tree for an \
+                             indented text tree like `cargo tree`, annotated with dependency \
+                             kinds, unicode / ascii for a layered diagram drawn with text, \
+                             graphml / gexf for analysis in tools like yEd or Gephi, d2 / \
+                             plantuml for diagram-as-code tools, dsm-csv / dsm-html for a \
+                             dependency structure matrix, csv / tsv for tables of packages and \
+                             dependencies (written to --output-dir), cypher for loading the \
+                             graph into Neo4j, or cyclonedx / spdx for a JSON SBOM",
+                    ),
+            )
+            .arg(
+                Arg::new("output_dir")
+                    .long("output-dir")
+                    .default_value(".")
+                    .value_name("DIR")
+                    .help(
+                        "Directory to write nodes.csv and edges.csv (or .tsv) to, with \
+                             --output-format csv / tsv",
+                    ),
+            )
+            .arg(
+                Arg::new("svg")
+                    .long("svg")
+                    .conflicts_with_all([
+                        "output_format",
+                        "legend",
+                        "rank_by",
+                        "cluster_workspaces",
+                    ])
+                    .value_name("PATH")
+                    .help(
+                        "Lay out the graph without Graphviz and write it to the given file \
+                             as SVG",
+                    ),
+            )
+            .arg(Arg::new("project").long("project").value_name("NAME").help(
+                "Tag packages and dependencies with a project identifier in the \
+                             cypher output, to load several projects into one database",
+            ))
+            .arg(
+                Arg::new("sqlite")
+                    .long("sqlite")
+                    .conflicts_with_all(["output_format", "svg"])
+                    .value_name("PATH")
+                    .help(
+                        "Write the packages, dependencies, enabled features and transitive \
+                             dependencies to a new SQLite database at the given path",
+                    ),
+            )
+            .arg(
+                Arg::new("color")
+                    .long("color")
+                    .value_parser(["auto", "always", "never"])
+                    .default_value("auto")
+                    .value_name("WHEN")
+                    .help(
+                        "Whether to color the unicode / ascii output (auto: if stdout is a \
+                             terminal and NO_COLOR is not set)",
+                    ),
+            )
+            .arg(
+                Arg::new("max_width")
+                    .long("max-width")
+                    .value_parser(value_parser!(usize))
+                    .value_name("COLUMNS")
+                    .help(
+                        "Maximum width of the unicode / ascii output; packages that don't \
+                             fit are left out (default: $COLUMNS, or 120)",
+                    ),
+            )
+            .arg(Arg::new("legend").long("legend").action(ArgAction::SetTrue).help(
+                "Add a legend explaining the node shapes, colors and line styles used \
+                             in the graph",
+            ))
+            .arg(
+                Arg::new("cluster_workspaces")
+                    .long("cluster-workspaces")
+                    .action(ArgAction::SetTrue)
+                    .help(
+                        "With multiple --manifest-path values, group the members of each \
+                             workspace and color them by workspace",
+                    ),
+            )
+            .arg(Arg::new("edge_labels").long("edge-labels").action(ArgAction::SetTrue).help(
+                "Label dependency edges with how the dependency is specified in \
+                             Cargo.toml: renames, version requirements and features",
+            ))
+            .arg(Arg::new("rank_by").long("rank-by").value_parser(["depth", "reverse-depth"]).help(
+                "Place packages at the same distance from the workspace members \
+                             (depth) or from packages without dependencies (reverse-depth) on \
+                             the same level",
+            ))
+            .arg(
+                Arg::new("rankdir")
+                    .long("rankdir")
+                    .value_parser(["TB", "LR", "BT", "RL"])
+                    .help("Direction of the graph layout, e.g. LR for left-to-right"),
+            )
+            .arg(Arg::new("svg_attrs").long("svg-attrs").action(ArgAction::SetTrue).help(
+                "Add links, tooltips and CSS classes to nodes and edges, for \
+                             rendering to SVG",
+            ))
+            .arg(
+                Arg::new("link_to")
+                    .long("link-to")
+                    .value_parser(["docs.rs", "crates.io"])
+                    .default_value("docs.rs")
+                    .requires("svg_attrs")
+                    .help("Where packages from crates.io should link to with --svg-attrs"),
+            )
+            .arg(
+                Arg::new("theme")
+                    .long("theme")
+                    .value_parser(BUILTIN_THEMES)
+                    .default_value("default")
+                    .value_name("THEME")
+                    .help("Built-in color theme to use"),
+            )
+            .arg(
+                Arg::new("theme_file")
+                    .long("theme-file")
+                    .conflicts_with("theme")
+                    .value_name("PATH")
+                    .help(
+                        "Path to a TOML file mapping dependency kinds and the other \
+                             properties of packages and dependencies to Graphviz attributes; see \
+                             the built-in themes in cargo-depgraph's repository for examples",
+                    ),
+            )
+            // Options to pass through to `cargo metadata`
+            .arg(
+                Arg::new("features")
+                    .long("features")
+                    .help("List of features to activate")
+                    .action(ArgAction::Append)
+                    .value_name("FEATURES"),
+            )
+            .arg(
+                Arg::new("all_features")
+                    .long("all-features")
+                    .action(ArgAction::SetTrue)
+                    .help("Activate all available features"),
+            )
+            .arg(
+                Arg::new("no_default_features")
+                    .long("no-default-features")
+                    .action(ArgAction::SetTrue)
+                    .help("Do not activate the `default` feature"),
+            )
+            .arg(
+                Arg::new("filter_platform")
+                    .long("filter-platform")
+                    .help("Only include resolve dependencies matching the given target-triple")
+                    .action(ArgAction::Append)
+                    .number_of_values(1)
+                    .value_name("TRIPLE"),
+            )
+            .arg(
+                Arg::new("manifest_path")
+                    .long("manifest-path")
+                    .help(
+                        "Path to Cargo.toml; can be given multiple times to combine the \
+                             graphs of several workspaces",
+                    )
+                    .action(ArgAction::Append)
+                    .number_of_values(1)
+                    .value_name("PATH"),
+            )
+            .arg(
+                Arg::new("frozen")
+                    .long("frozen")
+                    .action(ArgAction::SetTrue)
+                    .help("Require Cargo.lock and cache are up to date"),
+            )
+            .arg(
+                Arg::new("locked")
+                    .long("locked")
+                    .action(ArgAction::SetTrue)
+                    .help("Require Cargo.lock is up to date"),
+            )
+            .arg(
+                Arg::new("offline")
+                    .long("offline")
+                    .action(ArgAction::SetTrue)
+                    .help("Run without accessing the network"),
+            )
+            .arg(
+                Arg::new("unstable_flags")
+                    .short('Z')
+                    .help(
+                        "Unstable (nightly-only) flags to Cargo, see \
                             'cargo -Z help' for details",
-                        )
-                        .value_name("FLAG")
-                        .action(ArgAction::Append)
-                        .number_of_values(1),
-                ),
-        )
-        .get_matches();
-
-    let matches = matches.subcommand_matches("depgraph").unwrap();
+                    )
+                    .value_name("FLAG")
+                    .action(ArgAction::Append)
+                    .number_of_values(1),
+            )
+            .arg(
+                Arg::new("cache")
+                    .long("cache")
+                    .action(ArgAction::SetTrue)
+                    // Reusing the cached output would skip cargo's check of Cargo.lock
+                    .conflicts_with_all(["frozen", "locked"])
+                    .help(
+                        "Cache the output of `cargo metadata` in the target directory and \
+                         reuse it while Cargo.lock and the manifests are unchanged",
+                    ),
+            )
+            // Inputs other than `cargo metadata`
+            .arg(
+                Arg::new("from_sbom")
+                    .long("from-sbom")
+                    .conflicts_with_all(CARGO_METADATA_ARGS)
+                    .value_name("PATH")
+                    .help("Read the dependency graph from a CycloneDX or SPDX SBOM (JSON)"),
+            )
+            .arg(
+                Arg::new("from_binary")
+                    .long("from-binary")
+                    .conflicts_with_all(CARGO_METADATA_ARGS)
+                    .conflicts_with("from_sbom")
+                    .value_name("PATH")
+                    .help("Read the dependency graph from a binary built with `cargo auditable`"),
+            )
+            .arg(
+                Arg::new("lockfile")
+                    .long("lockfile")
+                    .conflicts_with_all(CARGO_METADATA_ARGS)
+                    .conflicts_with_all(["from_sbom", "from_binary"])
+                    .value_name("PATH")
+                    .help(
+                        "Read the dependency graph from a Cargo.lock file without running \
+                             cargo (dependency kinds are unknown)",
+                    ),
+            )
+            .arg(Arg::new("batch").long("batch").exclusive(true).value_name("PATH").help(
+                "Render all views listed in a TOML file, each with its own options \
+                             and output path",
+            )),
+    )
+}
 
+fn config_from_matches(matches: &ArgMatches) -> Config {
     let all_deps = matches.get_flag("all_deps");
     let build_deps = all_deps || matches.get_flag("build_deps");
     let dev_deps = all_deps || matches.get_flag("dev_deps");
     let target_deps = all_deps || matches.get_flag("target_deps");
     let dedup_transitive_deps = matches.get_flag("dedup_transitive_deps");
+    let merge_edges = matches.get_flag("merge_edges");
     let hide = matches.get_many("hide").map_or_else(Vec::new, collect_owned);
+    let prune = matches.get_many("prune").map_or_else(Vec::new, collect_owned);
     let exclude = matches.get_many("exclude").map_or_else(Vec::new, collect_owned);
     let include = matches.get_many("include").map_or_else(Vec::new, collect_owned);
+    let source_filter = matches.get_many("source_filter").map_or_else(Vec::new, collect_owned);
+    let exclude_source = matches.get_many("exclude_source").map_or_else(Vec::new, collect_owned);
     let root = matches.get_many("root").map_or_else(Vec::new, collect_owned);
     let workspace_only = matches.get_flag("workspace_only");
     let focus = matches.get_many("focus").map_or_else(Vec::new, collect_owned);
     let depth = matches.get_one("depth").copied();
+    let path_from = matches.get_many("path_from").map_or_else(Vec::new, collect_owned);
+    let path_to = matches.get_many("path_to").map_or_else(Vec::new, collect_owned);
+    let max_paths = matches.get_one("max_paths").copied();
+    let highlight_paths = matches.get_flag("highlight_paths");
+    let output_format = matches.get_one("output_format").cloned().unwrap();
+    let svg = matches.get_one("svg").cloned();
+    let output_dir = matches.get_one("output_dir").cloned().unwrap();
+    let sqlite = matches.get_one("sqlite").cloned();
+    let project = matches.get_one("project").cloned();
+    let legend = matches.get_flag("legend");
+    let cluster_workspaces = matches.get_flag("cluster_workspaces");
+    let edge_labels = matches.get_flag("edge_labels");
+    let rank_by = matches.get_one("rank_by").cloned();
+    let rankdir = matches.get_one("rankdir").cloned();
+    let svg_attrs = matches.get_flag("svg_attrs");
+    let link_to = matches.get_one("link_to").cloned().unwrap();
+    let theme = matches.get_one("theme").cloned().unwrap();
+    let theme_file = matches.get_one("theme_file").cloned();
+    let color = matches.get_one("color").cloned().unwrap();
+    let max_width = matches.get_one("max_width").copied();
 
     let features = matches.get_many("features").map_or_else(Vec::new, collect_owned);
     let all_features = matches.get_flag("all_features");
     let no_default_features = matches.get_flag("no_default_features");
     let filter_platform = matches.get_many("filter_platform").map_or_else(Vec::new, collect_owned);
-    let manifest_path = matches.get_one("manifest_path").cloned();
+    let manifest_path = matches.get_many("manifest_path").map_or_else(Vec::new, collect_owned);
     let frozen = matches.get_flag("frozen");
     let locked = matches.get_flag("locked");
     let offline = matches.get_flag("offline");
     let unstable_flags = matches.get_many("unstable_flags").map_or_else(Vec::new, collect_owned);
+    let cache = matches.get_flag("cache");
+
+    let from_sbom = matches.get_one("from_sbom").cloned();
+    let from_binary = matches.get_one("from_binary").cloned();
+    let lockfile = matches.get_one("lockfile").cloned();
+
+    let batch = matches.get_one("batch").cloned();
 
     Config {
         build_deps,
         dev_deps,
         target_deps,
         dedup_transitive_deps,
+        merge_edges,
         hide,
+        prune,
         exclude,
         include,
+        source_filter,
+        exclude_source,
         root,
         workspace_only,
         focus,
         depth,
+        path_from,
+        path_to,
+        max_paths,
+        highlight_paths,
+        output_format,
+        svg,
+        output_dir,
+        sqlite,
+        project,
+        legend,
+        cluster_workspaces,
+        edge_labels,
+        rank_by,
+        rankdir,
+        svg_attrs,
+        link_to,
+        theme,
+        theme_file,
+        color,
+        max_width,
         features,
         all_features,
         no_default_features,
@@ -240,6 +631,11 @@ pub(crate) fn parse_options() -> Config {
         locked,
         offline,
         unstable_flags,
+        cache,
+        from_sbom,
+        from_binary,
+        lockfile,
+        batch,
     }
 }
 
@@ -1,6 +1,52 @@
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign};
+use std::{
+    fmt::{self, Display, Formatter},
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
+};
 
-use cargo_metadata::DependencyKind as MetaDepKind;
+use cargo_metadata::{DependencyKind as MetaDepKind, semver::VersionReq};
+
+/// The data associated with a dependency graph edge
+#[derive(Clone, Debug)]
+pub(crate) struct Dependency {
+    pub info: DepInfo,
+
+    /// the kind of this dependency as declared by the dependent, unlike `info.kind` not updated
+    /// with the kinds of the dependent itself by update_dep_info
+    pub declared_kind: DepKind,
+
+    /// the name this dependency was given in the dependent's Cargo.toml, if it was renamed
+    pub rename: Option<String>,
+
+    /// the version requirement from the dependent's Cargo.toml (unknown for placeholder edges and
+    /// for edges merged from parts with different requirements)
+    pub req: Option<VersionReq>,
+
+    pub uses_default_features: bool,
+
+    /// features that are explicitly enabled in the dependent's Cargo.toml
+    pub features: Vec<String>,
+
+    /// the target triple or cfg() expression, for target-specific dependencies
+    pub target: Option<String>,
+
+    /// the parallel edges this edge was created from by --merge-edges
+    pub merged_from: Vec<Dependency>,
+}
+
+impl Dependency {
+    pub fn new(info: DepInfo) -> Self {
+        Self {
+            info,
+            declared_kind: info.kind,
+            rename: None,
+            req: None,
+            uses_default_features: true,
+            features: Vec::new(),
+            target: None,
+            merged_from: Vec::new(),
+        }
+    }
+}
 
 #[derive(Clone, Copy, Debug, Default)]
 pub(crate) struct DepInfo {
@@ -16,11 +62,25 @@ pub(crate) struct DepInfo {
     /// if optional, whether this is optional directly or transitively
     pub is_optional_direct: bool,
 
+    /// whether this is part of a path selected via --path-from and --path-to
+    pub is_on_path: bool,
+
     /// whether this edge has been updated by update_dep_info after being inserted into the graph
     // TODO: Store separately from DepInfo, make dedicated enum
     pub visited: bool,
 }
 
+impl DepInfo {
+    pub fn combine_incoming(&mut self, other: Self) {
+        self.is_target_dep &= other.is_target_dep;
+        self.is_optional &= other.is_optional;
+        // Only directly optional if all combined edges are, so the result of combining e.g. the
+        // edges replaced by a --prune placeholder doesn't depend on their order
+        self.is_optional_direct &= other.is_optional_direct;
+        self.kind.combine_incoming(other.kind);
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) struct DepKind {
     pub host: BuildFlag,
@@ -68,6 +128,21 @@ impl DepKind {
         }
     }
 
+    /// A kebab-case name for this kind, for use in identifiers and machine-readable output.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::NORMAL => "normal",
+            Self::DEV => "dev",
+            Self::BUILD => "build",
+            Self::BUILD_OF_DEV => "build-of-dev",
+            Self::NORMAL_AND_BUILD => "normal-and-build",
+            Self::DEV_AND_BUILD => "dev-and-build",
+            Self::NORMAL_AND_BUILD_OF_DEV => "normal-and-build-of-dev",
+            Self::DEV_AND_BUILD_OF_DEV => "dev-and-build-of-dev",
+            Self::UNKNOWN => "unknown",
+        }
+    }
+
     pub fn is_dev_only(&self) -> bool {
         self.host != BuildFlag::Always && self.target != BuildFlag::Always
     }
@@ -79,6 +154,24 @@ impl Default for DepKind {
     }
 }
 
+impl Display for DepKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            Self::NORMAL => "normal",
+            Self::DEV => "dev",
+            Self::BUILD => "build",
+            Self::BUILD_OF_DEV => "build of dev",
+            Self::NORMAL_AND_BUILD => "normal + build",
+            Self::DEV_AND_BUILD => "dev + build",
+            Self::NORMAL_AND_BUILD_OF_DEV => "normal + build of dev",
+            Self::DEV_AND_BUILD_OF_DEV => "dev + build of dev",
+            Self::UNKNOWN => "unknown",
+        };
+
+        f.write_str(s)
+    }
+}
+
 impl From<MetaDepKind> for DepKind {
     fn from(kind: MetaDepKind) -> Self {
         match kind {
@@ -97,6 +190,16 @@ pub enum BuildFlag {
     Never,
 }
 
+impl BuildFlag {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::Test => "test",
+            Self::Never => "never",
+        }
+    }
+}
+
 impl BitAnd for BuildFlag {
     type Output = Self;
 
@@ -1,18 +1,26 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry as HashMapEntry};
 
 use petgraph::{
     Direction,
     algo::all_simple_paths,
     stable_graph::{NodeIndex, StableDiGraph},
+    visit::EdgeRef,
 };
 
-use crate::{dep_info::DepInfo, package::Package};
+use crate::{
+    dep_info::{DepInfo, DepKind, Dependency},
+    package::{Package, PackageSource},
+};
 
 mod build;
+mod paths;
 
-pub(crate) use build::get_dep_graph;
+pub(crate) use self::{
+    build::{get_dep_graph, merge_workspaces},
+    paths::select_paths,
+};
 
-pub(crate) type DepGraph = StableDiGraph<Package, DepInfo, u16>;
+pub(crate) type DepGraph = StableDiGraph<Package, Dependency, u16>;
 
 pub(crate) fn update_dep_info(graph: &mut DepGraph) {
     for idx in graph.node_indices().collect::<Vec<_>>() {
@@ -30,18 +38,16 @@ fn update_node(graph: &mut DepGraph, idx: NodeIndex<u16>) {
     let mut node_info: Option<DepInfo> = None;
     while let Some((edge_idx, node_idx)) = incoming.next(graph) {
         // Don't backtrack on reverse dev-dependencies of workspace members
-        let ws_reverse_dev_dep = is_ws_member && graph[edge_idx].kind.is_dev_only();
+        let ws_reverse_dev_dep = is_ws_member && graph[edge_idx].info.kind.is_dev_only();
 
-        if !ws_reverse_dev_dep && !graph[edge_idx].visited {
+        if !ws_reverse_dev_dep && !graph[edge_idx].info.visited {
             update_node(graph, node_idx);
         }
 
-        let edge_info = graph[edge_idx];
+        let edge_info = graph[edge_idx].info;
 
         if let Some(i) = &mut node_info {
-            i.is_target_dep &= edge_info.is_target_dep;
-            i.is_optional &= edge_info.is_optional;
-            i.kind.combine_incoming(edge_info.kind);
+            i.combine_incoming(edge_info);
         } else {
             node_info = Some(edge_info);
         }
@@ -57,7 +63,7 @@ fn update_node(graph: &mut DepGraph, idx: NodeIndex<u16>) {
 
     let mut outgoing = graph.neighbors_directed(idx, Direction::Outgoing).detach();
     while let Some(edge_idx) = outgoing.next_edge(graph) {
-        let edge_info = &mut graph[edge_idx];
+        let edge_info = &mut graph[edge_idx].info;
 
         // it's unclear to me why this happens... maybe a bug in petgraph?
         if edge_info.visited {
@@ -127,6 +133,156 @@ pub(crate) fn remove_deps(graph: &mut DepGraph, hide: &[String]) {
     }
 }
 
+pub(crate) fn prune_deps(graph: &mut DepGraph, prune: &[String]) {
+    let pruned: Vec<_> =
+        graph.node_indices().filter(|&idx| prune.contains(&graph[idx].name)).collect();
+
+    for idx in pruned {
+        // A pruned package can itself be an exclusive dependency of a previously pruned package.
+        if !graph.contains_node(idx) {
+            continue;
+        }
+
+        // Everything that's still reachable from the roots without going through the pruned
+        // package is not exclusive to it.
+        let roots = graph.node_indices().filter(|&i| {
+            i != idx
+                && (graph[i].is_ws_member
+                    || graph.neighbors_directed(i, Direction::Incoming).next().is_none())
+        });
+        let shared = reachable(graph, roots, Some(idx));
+        let exclusive: Vec<_> = reachable(graph, [idx], None)
+            .into_iter()
+            .filter(|&i| i != idx && !shared.contains(&i))
+            .collect();
+
+        if exclusive.is_empty() {
+            continue;
+        }
+
+        let mut edge_info: Option<DepInfo> = None;
+        let mut declared_kind: Option<DepKind> = None;
+        let mut outgoing = graph.neighbors_directed(idx, Direction::Outgoing).detach();
+        while let Some((edge_idx, node_idx)) = outgoing.next(graph) {
+            if exclusive.contains(&node_idx) {
+                let Dependency { info, declared_kind: kind, .. } = graph[edge_idx];
+                edge_info.get_or_insert(info).combine_incoming(info);
+                declared_kind.get_or_insert(kind).combine_incoming(kind);
+            }
+        }
+
+        let mut node_info: Option<DepInfo> = None;
+        let mut count = 0;
+        for &i in &exclusive {
+            let pkg = graph.remove_node(i).unwrap();
+            node_info.get_or_insert(pkg.dep_info).combine_incoming(pkg.dep_info);
+            count += match pkg.source {
+                PackageSource::Placeholder(count) => count,
+                _ => 1,
+            };
+        }
+
+        let placeholder = Package::placeholder(count, node_info.unwrap(), &graph[idx].id);
+        let placeholder = graph.add_node(placeholder);
+        let mut dependency = Dependency::new(edge_info.unwrap());
+        dependency.declared_kind = declared_kind.unwrap();
+        graph.add_edge(idx, placeholder, dependency);
+    }
+}
+
+/// Computes the distance of every node from the roots of the graph (workspace members and packages
+/// that nothing depends on), or if `reverse` is `true`, from the leaves (packages that don't have
+/// any dependencies).
+///
+/// Nodes that can't be reached from any root / can't reach any leaf are not part of the result.
+pub(crate) fn node_depths(graph: &DepGraph, reverse: bool) -> HashMap<NodeIndex<u16>, u32> {
+    let direction = if reverse { Direction::Incoming } else { Direction::Outgoing };
+
+    let mut depths = HashMap::new();
+    let mut visit_queue: VecDeque<_> = graph
+        .node_indices()
+        .filter(|&idx| {
+            (!reverse && graph[idx].is_ws_member)
+                || graph.neighbors_directed(idx, direction.opposite()).next().is_none()
+        })
+        .map(|idx| (idx, 0))
+        .collect();
+
+    while let Some((idx, depth)) = visit_queue.pop_front() {
+        if let HashMapEntry::Vacant(v) = depths.entry(idx) {
+            v.insert(depth);
+            visit_queue.extend(graph.neighbors_directed(idx, direction).map(|i| (i, depth + 1)));
+        }
+    }
+
+    depths
+}
+
+/// Collects all nodes reachable from `start` (including `start` itself), without visiting `skip`.
+pub(crate) fn reachable(
+    graph: &DepGraph,
+    start: impl IntoIterator<Item = NodeIndex<u16>>,
+    skip: Option<NodeIndex<u16>>,
+) -> HashSet<NodeIndex<u16>> {
+    let mut visited = HashSet::new();
+    let mut visit_queue: VecDeque<_> = start.into_iter().collect();
+    while let Some(idx) = visit_queue.pop_front() {
+        if Some(idx) == skip || !visited.insert(idx) {
+            continue;
+        }
+
+        visit_queue.extend(graph.neighbors_directed(idx, Direction::Outgoing));
+    }
+
+    visited
+}
+
+pub(crate) fn merge_parallel_edges(graph: &mut DepGraph) {
+    for idx in graph.node_indices().collect::<Vec<_>>() {
+        let mut neighbors: Vec<_> = graph.neighbors_directed(idx, Direction::Outgoing).collect();
+        neighbors.sort();
+        neighbors.dedup();
+
+        for neighbor in neighbors {
+            let edges: Vec<_> = graph.edges_connecting(idx, neighbor).map(|e| e.id()).collect();
+            if edges.len() < 2 {
+                continue;
+            }
+
+            let mut parts: Vec<_> =
+                edges.into_iter().map(|e| graph.remove_edge(e).unwrap()).collect();
+            parts.sort_by_cached_key(|dep| (dep.info.kind.to_string(), dep.target.clone()));
+
+            // Fields that differ between the parts are unknown for the merged edge, except for
+            // features, which cargo unifies
+            let mut merged = parts[0].clone();
+            for part in &parts[1..] {
+                merged.info.combine_incoming(part.info);
+                merged.declared_kind.combine_incoming(part.declared_kind);
+                merged.info.is_on_path |= part.info.is_on_path;
+                if merged.target != part.target {
+                    merged.target = None;
+                }
+                if merged.rename != part.rename {
+                    merged.rename = None;
+                }
+                if merged.req != part.req {
+                    merged.req = None;
+                }
+                merged.uses_default_features |= part.uses_default_features;
+                for feature in &part.features {
+                    if !merged.features.contains(feature) {
+                        merged.features.push(feature.clone());
+                    }
+                }
+            }
+            merged.merged_from = parts;
+
+            graph.add_edge(idx, neighbor, merged);
+        }
+    }
+}
+
 pub(crate) fn dedup_transitive_deps(graph: &mut DepGraph) {
     for idx in graph.node_indices().collect::<Vec<_>>() {
         // We're only removing nodes, not adding new ones, so we can use the node indices collected
@@ -146,3 +302,168 @@ pub(crate) fn dedup_transitive_deps(graph: &mut DepGraph) {
         }
     }
 }
+
+/// Builds a graph from a list of dependencies between packages, given by name. `members` are the
+/// workspace members, the kinds are propagated from them with update_dep_info.
+#[cfg(test)]
+pub(crate) fn test_graph(members: &[&str], deps: &[(&str, &str, DepKind)]) -> DepGraph {
+    let mut graph = DepGraph::default();
+    let mut indices = HashMap::new();
+    let names = members.iter().chain(deps.iter().flat_map(|(from, to, _)| [from, to]));
+    for &name in names {
+        indices
+            .entry(name)
+            .or_insert_with(|| graph.add_node(Package::test(name, members.contains(&name))));
+    }
+    for &(from, to, kind) in deps {
+        let info = DepInfo { kind, ..DepInfo::default() };
+        graph.add_edge(indices[from], indices[to], Dependency::new(info));
+    }
+
+    update_dep_info(&mut graph);
+    graph
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn find(graph: &DepGraph, name: &str) -> NodeIndex<u16> {
+        graph.node_indices().find(|&idx| graph[idx].name == name).unwrap()
+    }
+
+    /// app -> util as a normal dependency with feature `a` and as a dev-dependency with feature `b`
+    /// and without default features
+    fn parallel_edges() -> DepGraph {
+        let mut graph = test_graph(
+            &["app"],
+            &[("app", "util", DepKind::NORMAL), ("app", "util", DepKind::DEV)],
+        );
+        for edge in graph.edge_weights_mut() {
+            if edge.info.kind == DepKind::NORMAL {
+                edge.req = Some("^0.1".parse().unwrap());
+                edge.features = vec!["a".to_owned()];
+            } else {
+                edge.req = Some("=0.1.0".parse().unwrap());
+                edge.uses_default_features = false;
+                edge.features = vec!["b".to_owned()];
+            }
+        }
+        graph
+    }
+
+    #[test]
+    fn merge_parallel_edges_combines_parts() {
+        let mut graph = parallel_edges();
+        merge_parallel_edges(&mut graph);
+
+        assert_eq!(graph.edge_count(), 1);
+        let merged = graph.edge_weights().next().unwrap();
+        assert_eq!(merged.info.kind, DepKind::NORMAL);
+        assert_eq!(merged.merged_from.len(), 2);
+        assert_eq!(merged.req, None);
+        assert!(merged.uses_default_features);
+        let features: HashSet<_> = merged.features.iter().map(String::as_str).collect();
+        assert_eq!(features, HashSet::from(["a", "b"]));
+
+        let parts: Vec<_> = merged.merged_from.iter().map(|part| part.info.kind).collect();
+        assert_eq!(parts, [DepKind::DEV, DepKind::NORMAL]);
+        assert_eq!(merged.merged_from[0].features, ["b"]);
+    }
+
+    #[test]
+    fn merge_parallel_edges_keeps_equal_fields() {
+        let mut graph = parallel_edges();
+        for edge in graph.edge_weights_mut() {
+            edge.rename = Some("utils".to_owned());
+            edge.req = Some("^0.1".parse().unwrap());
+            edge.target = Some("cfg(unix)".to_owned());
+        }
+        merge_parallel_edges(&mut graph);
+
+        let merged = graph.edge_weights().next().unwrap();
+        assert_eq!(merged.rename.as_deref(), Some("utils"));
+        assert_eq!(merged.req, Some("^0.1".parse().unwrap()));
+        assert_eq!(merged.target.as_deref(), Some("cfg(unix)"));
+    }
+
+    #[test]
+    fn prune_deps_keeps_declared_kinds() {
+        let mut graph = test_graph(
+            &["app"],
+            &[
+                ("app", "tester", DepKind::DEV),
+                ("tester", "a", DepKind::NORMAL),
+                ("tester", "b", DepKind::BUILD),
+            ],
+        );
+        prune_deps(&mut graph, &["tester".to_owned()]);
+
+        let tester = find(&graph, "tester");
+        let edge = graph.edges_directed(tester, Direction::Outgoing).next().unwrap();
+        assert_eq!(graph[edge.target()].source, PackageSource::Placeholder(2));
+        assert_eq!(edge.weight().declared_kind, DepKind::NORMAL_AND_BUILD);
+        assert_eq!(edge.weight().info.kind, DepKind::DEV_AND_BUILD_OF_DEV);
+    }
+
+    #[test]
+    fn placeholder_edge_is_only_optional_if_all_pruned_edges_are() {
+        // The pruned edges are combined in both orders, only tester -> a is optional
+        for deps in [["a", "b"], ["b", "a"]] {
+            let mut graph = test_graph(
+                &["app"],
+                &[
+                    ("app", "tester", DepKind::NORMAL),
+                    ("tester", deps[0], DepKind::NORMAL),
+                    ("tester", deps[1], DepKind::NORMAL),
+                ],
+            );
+            let a = find(&graph, "a");
+            let edge = graph.edges_directed(a, Direction::Incoming).next().unwrap().id();
+            graph[edge].info.is_optional = true;
+            graph[edge].info.is_optional_direct = true;
+            prune_deps(&mut graph, &["tester".to_owned()]);
+
+            let tester = find(&graph, "tester");
+            let edge = graph.edges_directed(tester, Direction::Outgoing).next().unwrap();
+            assert!(!edge.weight().info.is_optional);
+            assert!(!edge.weight().info.is_optional_direct);
+        }
+    }
+
+    /// app -> a -> b -> c and app -> c, with the workspace member cli depending on app
+    fn chain_with_shortcut() -> DepGraph {
+        test_graph(
+            &["cli", "app"],
+            &[
+                ("cli", "app", DepKind::NORMAL),
+                ("app", "a", DepKind::NORMAL),
+                ("a", "b", DepKind::NORMAL),
+                ("b", "c", DepKind::NORMAL),
+                ("app", "c", DepKind::NORMAL),
+            ],
+        )
+    }
+
+    fn depths(graph: &DepGraph, reverse: bool) -> Vec<(&str, u32)> {
+        let depths = node_depths(graph, reverse);
+        let mut depths: Vec<_> =
+            depths.into_iter().map(|(idx, depth)| (graph[idx].name.as_str(), depth)).collect();
+        depths.sort_unstable();
+        depths
+    }
+
+    #[test]
+    fn node_depths_from_the_roots() {
+        // Workspace members are roots even if other packages depend on them, and the shortest
+        // path counts
+        let graph = chain_with_shortcut();
+        assert_eq!(depths(&graph, false), [("a", 1), ("app", 0), ("b", 2), ("c", 1), ("cli", 0)]);
+    }
+
+    #[test]
+    fn node_depths_from_the_leaves() {
+        let graph = chain_with_shortcut();
+        assert_eq!(depths(&graph, true), [("a", 2), ("app", 1), ("b", 1), ("c", 0), ("cli", 2)]);
+    }
+}
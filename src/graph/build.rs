@@ -1,17 +1,26 @@
-use std::collections::{HashMap, VecDeque, hash_map::Entry as HashMapEntry};
+use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry as HashMapEntry};
 
-use anyhow::Context;
-use cargo_metadata::{DependencyKind as MetaDepKind, Metadata, Package as MetaPackage, PackageId};
+use anyhow::{Context, bail};
+use cargo_metadata::{
+    DependencyKind as MetaDepKind, Metadata, Package as MetaPackage, PackageId, Resolve,
+};
 
 use super::DepGraph;
 use crate::{
     cli::Config,
-    dep_info::{DepInfo, DepKind},
-    package::Package,
+    dep_info::{DepInfo, DepKind, Dependency},
+    package::{Package, PackageSource},
     util::is_proc_macro,
 };
 
-pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Result<DepGraph> {
+/// Builds the dependency graph from `cargo metadata` output. `workspaces` maps the workspace
+/// members to the index of the workspace they belong to, if the metadata of several workspaces
+/// was merged.
+pub(crate) fn get_dep_graph(
+    metadata: Metadata,
+    workspaces: &HashMap<PackageId, usize>,
+    config: &Config,
+) -> anyhow::Result<DepGraph> {
     let resolve = metadata
         .resolve
         .context("Couldn't obtain dependency graph. Your cargo version may be too old.")?;
@@ -43,7 +52,9 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
             continue;
         }
 
-        let node_idx = graph.add_node(Package::new(pkg, true));
+        let mut package = Package::new(pkg, true);
+        package.workspace = workspaces.get(pkg_id).copied();
+        let node_idx = graph.add_node(package);
         deps_add_queue.push_back((pkg_id.clone(), 0_u32));
         let old_val = node_indices.insert(pkg_id.clone(), node_idx);
         assert!(old_val.is_none());
@@ -62,6 +73,7 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
             .iter()
             .find(|n| n.id == pkg_id)
             .context("package not found in resolve")?;
+        graph[parent_idx].features = resolve_node.features.clone();
 
         for dep in &resolve_node.deps {
             // Same as dep.name in most cases, but not if it got renamed in parent's Cargo.toml
@@ -94,7 +106,8 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
                     }
 
                     let dep_pkg = &get_package(&metadata.packages, &dep.pkg);
-                    let dep_pkg = Package::new(dep_pkg, is_workspace_member);
+                    let mut dep_pkg = Package::new(dep_pkg, is_workspace_member);
+                    dep_pkg.workspace = workspaces.get(&dep.pkg).copied();
 
                     // proc-macros are a bit weird because Cargo doesn't report
                     // them as build dependencies when really they are.
@@ -102,6 +115,12 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
                         continue;
                     }
 
+                    // Source filters are specified and this package doesn't match them.
+                    // Workspace members are never filtered out by their source.
+                    if !is_workspace_member && skip_source(config, &dep_pkg.source) {
+                        continue;
+                    }
+
                     let idx = graph.add_node(dep_pkg);
 
                     deps_add_queue.push_back((dep.pkg.clone(), depth + 1));
@@ -120,17 +139,14 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
                         && d.target.as_ref().map(|t| t.to_string())
                             == info.target.as_ref().map(|t| t.to_string())
                 });
-                let is_optional = match extra {
-                    Some(dep) => dep.optional,
-                    None => {
-                        eprintln!(
-                            "dependency {} of {} not found in packages \
-                             => dependencies, this should never happen!",
-                            dep_crate_name, pkg.name,
-                        );
-                        false
-                    }
-                };
+                if extra.is_none() {
+                    eprintln!(
+                        "dependency {} of {} not found in packages \
+                         => dependencies, this should never happen!",
+                        dep_crate_name, pkg.name,
+                    );
+                }
+                let is_optional = extra.is_some_and(|dep| dep.optional);
 
                 // We checked whether to skip this dependency fully above, but if there's
                 // multiple dependencies from A to B (e.g. normal dependency with no features,
@@ -140,24 +156,105 @@ pub(crate) fn get_dep_graph(metadata: Metadata, config: &Config) -> anyhow::Resu
                     continue;
                 }
 
-                graph.add_edge(
-                    parent_idx,
-                    child_idx,
-                    DepInfo {
-                        kind: DepKind::new(info.kind, child_is_proc_macro),
-                        is_target_dep: info.target.is_some(),
-                        is_optional,
-                        is_optional_direct: is_optional,
-                        visited: false,
-                    },
-                );
+                let mut dependency = Dependency::new(DepInfo {
+                    kind: DepKind::new(info.kind, child_is_proc_macro),
+                    is_target_dep: info.target.is_some(),
+                    is_optional,
+                    is_optional_direct: is_optional,
+                    is_on_path: false,
+                    visited: false,
+                });
+                dependency.target = info.target.as_ref().map(|t| t.to_string());
+                if let Some(extra) = extra {
+                    dependency.rename = extra.rename.clone();
+                    dependency.req = Some(extra.req.clone());
+                    dependency.uses_default_features = extra.uses_default_features;
+                    dependency.features = extra.features.clone();
+                }
+
+                graph.add_edge(parent_idx, child_idx, dependency);
             }
         }
     }
 
+    // The outputs identify packages by their ID, so two different packages with the same ID
+    // (e.g. from combining workspaces) would be merged or break them.
+    let mut ids = HashSet::new();
+    if let Some(pkg) = graph.node_weights().find(|pkg| !ids.insert(&pkg.id)) {
+        bail!("multiple packages have the ID `{}`", pkg.id);
+    }
+
     Ok(graph)
 }
 
+/// Combines the metadata of several workspaces, such that packages they have in common (by
+/// `PackageId`) only appear once. The resolved dependencies of common packages are merged, as they
+/// can differ between workspaces, e.g. because of different features.
+///
+/// Also returns the index of the workspace every workspace member belongs to.
+pub(crate) fn merge_workspaces(
+    workspaces: Vec<Metadata>,
+) -> anyhow::Result<(Metadata, HashMap<PackageId, usize>)> {
+    let mut workspaces = workspaces.into_iter();
+    let mut merged = workspaces.next().context("no workspaces to merge")?;
+    let mut members: HashMap<_, _> =
+        merged.workspace_members.iter().map(|id| (id.clone(), 0)).collect();
+    let merged_resolve = merged
+        .resolve
+        .as_mut()
+        .context("Couldn't obtain dependency graph. Your cargo version may be too old.")?;
+
+    for (i, metadata) in workspaces.enumerate() {
+        for pkg in metadata.packages {
+            if !merged.packages.iter().any(|p| p.id == pkg.id) {
+                merged.packages.push(pkg);
+            }
+        }
+        for id in metadata.workspace_members {
+            if let HashMapEntry::Vacant(v) = members.entry(id.clone()) {
+                v.insert(i + 1);
+                merged.workspace_members.push(id);
+            }
+        }
+
+        let Resolve { nodes, .. } = metadata
+            .resolve
+            .context("Couldn't obtain dependency graph. Your cargo version may be too old.")?;
+        for node in nodes {
+            let Some(merged_node) = merged_resolve.nodes.iter_mut().find(|n| n.id == node.id)
+            else {
+                merged_resolve.nodes.push(node);
+                continue;
+            };
+
+            for dep in node.deps {
+                match merged_node.deps.iter_mut().find(|d| d.pkg == dep.pkg) {
+                    Some(merged_dep) => {
+                        for info in dep.dep_kinds {
+                            if !merged_dep
+                                .dep_kinds
+                                .iter()
+                                .any(|i| i.kind == info.kind && i.target == info.target)
+                            {
+                                merged_dep.dep_kinds.push(info);
+                            }
+                        }
+                    }
+                    None => merged_node.deps.push(dep),
+                }
+            }
+            for feature in node.features {
+                if !merged_node.features.contains(&feature) {
+                    merged_node.features.push(feature);
+                }
+            }
+        }
+    }
+    merged_resolve.root = None;
+
+    Ok((merged, members))
+}
+
 fn get_package<'a>(packages: &'a [MetaPackage], pkg_id: &PackageId) -> &'a MetaPackage {
     packages.iter().find(|pkg| pkg.id == *pkg_id).unwrap()
 }
@@ -167,3 +264,279 @@ pub(crate) fn skip_dep(config: &Config, info: &cargo_metadata::DepKindInfo) -> b
         || (!config.dev_deps && info.kind == MetaDepKind::Development)
         || (!config.target_deps && info.target.is_some())
 }
+
+fn skip_source(config: &Config, source: &PackageSource) -> bool {
+    (!config.source_filter.is_empty() && !config.source_filter.iter().any(|s| source.matches(s)))
+        || config.exclude_source.iter().any(|s| source.matches(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+    use crate::cli::parse_view_options;
+
+    /// A package from a local path if `dir` is given, otherwise from crates.io
+    struct TestPackage<'a> {
+        name: &'a str,
+        dir: Option<&'a str>,
+        deps: &'a [(&'a str, &'a str)],
+        features: &'a [&'a str],
+    }
+
+    fn id(name: &str, dir: Option<&str>) -> String {
+        match dir {
+            Some(dir) => format!("path+file://{dir}#{name}@0.1.0"),
+            None => format!("registry+https://github.com/rust-lang/crates.io-index#{name}@0.1.0"),
+        }
+    }
+
+    /// Creates the metadata of a workspace whose members are the packages from local paths.
+    /// Dependencies are given by name and kind, and refer to the first package with that name.
+    fn metadata(packages: &[TestPackage<'_>]) -> Metadata {
+        let find = |name: &str| packages.iter().find(|p| p.name == name).unwrap();
+        let source = |pkg: &TestPackage<'_>| {
+            pkg.dir.is_none().then_some("registry+https://github.com/rust-lang/crates.io-index")
+        };
+
+        let meta_packages: Vec<Value> = packages
+            .iter()
+            .map(|pkg| {
+                let deps: Vec<_> = pkg
+                    .deps
+                    .iter()
+                    .map(|&(name, kind)| {
+                        json!({
+                            "name": name,
+                            "source": source(find(name)),
+                            "req": "*",
+                            "kind": kind,
+                            "optional": false,
+                            "uses_default_features": true,
+                            "features": [],
+                        })
+                    })
+                    .collect();
+                let dir = pkg.dir.unwrap_or("/registry");
+                json!({
+                    "name": pkg.name,
+                    "version": "0.1.0",
+                    "id": id(pkg.name, pkg.dir),
+                    "source": source(pkg),
+                    "dependencies": deps,
+                    "targets": [],
+                    "features": {},
+                    "manifest_path": format!("{dir}/Cargo.toml"),
+                })
+            })
+            .collect();
+        let nodes: Vec<Value> = packages
+            .iter()
+            .map(|pkg| {
+                let deps: Vec<_> = pkg
+                    .deps
+                    .iter()
+                    .map(|&(name, kind)| {
+                        json!({
+                            "name": name,
+                            "pkg": id(name, find(name).dir),
+                            "dep_kinds": [{ "kind": kind }],
+                        })
+                    })
+                    .collect();
+                json!({
+                    "id": id(pkg.name, pkg.dir),
+                    "deps": deps,
+                    "dependencies": [],
+                    "features": pkg.features,
+                })
+            })
+            .collect();
+        let members: Vec<_> =
+            packages.iter().filter(|p| p.dir.is_some()).map(|p| id(p.name, p.dir)).collect();
+
+        serde_json::from_value(json!({
+            "packages": meta_packages,
+            "workspace_members": members,
+            "resolve": { "nodes": nodes, "root": null },
+            "workspace_root": "/",
+            "target_directory": "/target",
+            "version": 1,
+        }))
+        .unwrap()
+    }
+
+    fn config(args: &[&str]) -> Config {
+        let args: Vec<_> = args.iter().map(|&arg| arg.to_owned()).collect();
+        parse_view_options(&args).unwrap()
+    }
+
+    #[test]
+    fn merge_workspaces_combines_shared_packages() {
+        let a = metadata(&[
+            TestPackage {
+                name: "app-a",
+                dir: Some("/a"),
+                deps: &[("log", "normal")],
+                features: &[],
+            },
+            TestPackage { name: "log", dir: None, deps: &[], features: &["std"] },
+        ]);
+        let b = metadata(&[
+            TestPackage { name: "app-b", dir: Some("/b"), deps: &[("log", "dev")], features: &[] },
+            TestPackage { name: "log", dir: None, deps: &[], features: &["kv"] },
+        ]);
+
+        let (merged, workspaces) = merge_workspaces(vec![a, b]).unwrap();
+        assert_eq!(merged.packages.len(), 3);
+        assert_eq!(workspaces[&PackageId { repr: id("app-a", Some("/a")) }], 0);
+        assert_eq!(workspaces[&PackageId { repr: id("app-b", Some("/b")) }], 1);
+        assert!(!workspaces.contains_key(&PackageId { repr: id("log", None) }));
+
+        let resolve = merged.resolve.as_ref().unwrap();
+        let log = resolve.nodes.iter().find(|n| n.id.repr == id("log", None)).unwrap();
+        assert_eq!(log.features, ["std", "kv"]);
+
+        let graph = get_dep_graph(merged, &workspaces, &config(&["--dev-deps"])).unwrap();
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn merge_workspaces_merges_dependency_kinds() {
+        let a = metadata(&[
+            TestPackage { name: "app", dir: Some("/a"), deps: &[("log", "normal")], features: &[] },
+            TestPackage { name: "log", dir: None, deps: &[], features: &[] },
+        ]);
+        let b = metadata(&[
+            TestPackage { name: "app", dir: Some("/a"), deps: &[("log", "build")], features: &[] },
+            TestPackage { name: "log", dir: None, deps: &[], features: &[] },
+        ]);
+
+        let (merged, _) = merge_workspaces(vec![a, b]).unwrap();
+        let resolve = merged.resolve.as_ref().unwrap();
+        let app = resolve.nodes.iter().find(|n| n.id.repr == id("app", Some("/a"))).unwrap();
+        let kinds: Vec<_> = app.deps[0].dep_kinds.iter().map(|info| info.kind).collect();
+        assert_eq!(kinds, [MetaDepKind::Normal, MetaDepKind::Build]);
+    }
+
+    #[test]
+    fn merge_workspaces_keeps_path_packages_with_the_same_name_apart() {
+        let a = metadata(&[
+            TestPackage {
+                name: "app-a",
+                dir: Some("/a"),
+                deps: &[("util", "normal")],
+                features: &[],
+            },
+            TestPackage { name: "util", dir: Some("/a/util"), deps: &[], features: &[] },
+        ]);
+        let b = metadata(&[
+            TestPackage {
+                name: "app-b",
+                dir: Some("/b"),
+                deps: &[("util", "normal")],
+                features: &[],
+            },
+            TestPackage { name: "util", dir: Some("/b/util"), deps: &[], features: &[] },
+        ]);
+
+        let (merged, workspaces) = merge_workspaces(vec![a, b]).unwrap();
+        let graph = get_dep_graph(merged, &workspaces, &config(&[])).unwrap();
+        let ids: HashSet<_> = graph.node_weights().map(|pkg| pkg.id.as_str()).collect();
+        assert_eq!(ids.len(), 4);
+        assert!(ids.contains("util@0.1.0 (path+/a/util)"));
+        assert!(ids.contains("util@0.1.0 (path+/b/util)"));
+    }
+
+    #[test]
+    fn duplicate_package_ids_are_rejected() {
+        // Two packages with different cargo IDs but the same name, version and directory
+        let mut metadata = metadata(&[
+            TestPackage {
+                name: "app",
+                dir: Some("/a"),
+                deps: &[("util", "normal")],
+                features: &[],
+            },
+            TestPackage { name: "util", dir: Some("/a/util"), deps: &[], features: &[] },
+        ]);
+        let mut copy = metadata.packages[0].clone();
+        copy.id = PackageId { repr: "copy".to_owned() };
+        metadata.workspace_members.push(copy.id.clone());
+        let mut node = metadata.resolve.as_ref().unwrap().nodes[0].clone();
+        node.id = copy.id.clone();
+        metadata.resolve.as_mut().unwrap().nodes.push(node);
+        metadata.packages.push(copy);
+
+        let err = get_dep_graph(metadata, &HashMap::new(), &config(&[])).unwrap_err();
+        assert_eq!(err.to_string(), "multiple packages have the ID `app@0.1.0 (path+/a)`");
+    }
+
+    #[test]
+    fn source_filters() {
+        let crates_io = PackageSource::CratesIo;
+        let registry = PackageSource::Registry("https://my-registry.example.com/index/".to_owned());
+        let git =
+            PackageSource::Git("https://github.com/rust-lang/log?rev=0123abc#0123abc".to_owned());
+        let sources = [crates_io, registry, git, PackageSource::Path];
+        let skipped = |args: &[&str]| {
+            let config = config(args);
+            sources.iter().map(|source| skip_source(&config, source)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(skipped(&[]), [false, false, false, false]);
+        assert_eq!(skipped(&["--source-filter", "registry"]), [false, false, true, true]);
+        assert_eq!(skipped(&["--source-filter", "alt-registry,git"]), [true, false, false, true]);
+        assert_eq!(skipped(&["--exclude-source", "crates-io"]), [true, false, false, false]);
+        assert_eq!(
+            skipped(&["--source-filter", "registry", "--exclude-source", "alt-registry"]),
+            [false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn edges_carry_the_manifest_entry() {
+        let mut metadata = metadata(&[
+            TestPackage { name: "app", dir: Some("/a"), deps: &[("log", "normal")], features: &[] },
+            TestPackage { name: "log", dir: None, deps: &[], features: &[] },
+        ]);
+        // `logging = { package = "log", version = "0.4", default-features = false,
+        // features = ["std"] }` in `[target.'cfg(windows)'.dependencies]`
+        let dep = &mut metadata.packages[0].dependencies[0];
+        dep.rename = Some("logging".to_owned());
+        dep.req = "^0.4".parse().unwrap();
+        dep.uses_default_features = false;
+        dep.features = vec!["std".to_owned()];
+        dep.target = Some(serde_json::from_value(json!("cfg(windows)")).unwrap());
+        let node_dep = &mut metadata.resolve.as_mut().unwrap().nodes[0].deps[0];
+        node_dep.name = "logging".to_owned();
+        node_dep.dep_kinds[0].target = dep.target.clone();
+
+        let graph = get_dep_graph(metadata, &HashMap::new(), &config(&["--target-deps"])).unwrap();
+        let edge = graph.edge_weights().next().unwrap();
+        assert_eq!(edge.rename.as_deref(), Some("logging"));
+        assert_eq!(edge.req.as_ref().map(ToString::to_string).as_deref(), Some("^0.4"));
+        assert!(!edge.uses_default_features);
+        assert_eq!(edge.features, ["std"]);
+        assert_eq!(edge.target.as_deref(), Some("cfg(windows)"));
+        assert!(edge.info.is_target_dep);
+    }
+
+    #[test]
+    fn edges_without_manifest_details() {
+        let metadata = metadata(&[
+            TestPackage { name: "app", dir: Some("/a"), deps: &[("log", "normal")], features: &[] },
+            TestPackage { name: "log", dir: None, deps: &[], features: &[] },
+        ]);
+
+        let graph = get_dep_graph(metadata, &HashMap::new(), &config(&[])).unwrap();
+        let edge = graph.edge_weights().next().unwrap();
+        assert_eq!(edge.rename, None);
+        assert_eq!(edge.req.as_ref().map(ToString::to_string).as_deref(), Some("*"));
+        assert!(edge.uses_default_features);
+        assert!(edge.features.is_empty());
+        assert_eq!(edge.target, None);
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use anyhow::bail;
+use petgraph::{
+    Direction,
+    stable_graph::NodeIndex,
+    visit::{EdgeRef, IntoEdgeReferences},
+};
+
+use super::DepGraph;
+
+/// Marks all nodes and edges that are part of a path from one of the `from` packages to one of
+/// the `to` packages. If `max_paths` is given, only that many of the shortest paths are taken into
+/// account.
+///
+/// If `highlight` is `false`, everything that is not part of such a path is removed from the graph.
+pub(crate) fn select_paths(
+    graph: &mut DepGraph,
+    from: &[String],
+    to: &[String],
+    max_paths: Option<usize>,
+    highlight: bool,
+) -> anyhow::Result<()> {
+    let from_nodes = find_nodes(graph, from)?;
+    let to_nodes = find_nodes(graph, to)?;
+
+    let (path_nodes, path_edges) = match max_paths {
+        Some(max) => {
+            let paths = shortest_paths(graph, &from_nodes, &to_nodes, max);
+
+            let mut path_nodes = HashSet::new();
+            let mut path_edges = HashSet::new();
+            for path in &paths {
+                path_nodes.extend(path.iter().copied());
+                for pair in path.windows(2) {
+                    // There can be multiple edges between the same pair of packages, e.g. when a
+                    // crate is both a normal and a build-dependency. All of them are part of the
+                    // path.
+                    path_edges.extend(graph.edges_connecting(pair[0], pair[1]).map(|e| e.id()));
+                }
+            }
+            (path_nodes, path_edges)
+        }
+        None => {
+            // A node is on a path if it can be reached from one of the `from` packages and can
+            // reach one of the `to` packages, which is much cheaper than enumerating all paths.
+            let reached = reachable_directed(graph, &from_nodes, Direction::Outgoing);
+            let reaching = reachable_directed(graph, &to_nodes, Direction::Incoming);
+
+            // Paths don't go back to where they started or continue after they arrived, which
+            // would only be possible through dev-dependency cycles
+            let path_edges: HashSet<_> = graph
+                .edge_references()
+                .filter(|e| reached.contains(&e.source()) && reaching.contains(&e.target()))
+                .filter(|e| !from_nodes.contains(&e.target()) && !to_nodes.contains(&e.source()))
+                .map(|e| e.id())
+                .collect();
+            let path_nodes = path_edges
+                .iter()
+                .flat_map(|&e| {
+                    let (source, target) = graph.edge_endpoints(e).unwrap();
+                    [source, target]
+                })
+                .collect();
+            (path_nodes, path_edges)
+        }
+    };
+
+    for idx in graph.node_indices().collect::<Vec<_>>() {
+        if path_nodes.contains(&idx) {
+            graph[idx].dep_info.is_on_path = true;
+        } else if !highlight {
+            graph.remove_node(idx);
+        }
+    }
+
+    for edge_idx in graph.edge_indices().collect::<Vec<_>>() {
+        if path_edges.contains(&edge_idx) {
+            graph[edge_idx].info.is_on_path = true;
+        } else if !highlight {
+            graph.remove_edge(edge_idx);
+        }
+    }
+
+    Ok(())
+}
+
+fn find_nodes(graph: &DepGraph, names: &[String]) -> anyhow::Result<Vec<NodeIndex<u16>>> {
+    let nodes: Vec<_> =
+        graph.node_indices().filter(|&idx| names.contains(&graph[idx].name)).collect();
+    if nodes.is_empty() {
+        bail!("none of the packages {names:?} are part of the dependency graph");
+    }
+
+    Ok(nodes)
+}
+
+/// Collects all nodes reachable from `start` (including `start` itself) in the given direction.
+fn reachable_directed(
+    graph: &DepGraph,
+    start: &[NodeIndex<u16>],
+    direction: Direction,
+) -> HashSet<NodeIndex<u16>> {
+    let mut visited = HashSet::new();
+    let mut visit_queue: VecDeque<_> = start.iter().copied().collect();
+    while let Some(idx) = visit_queue.pop_front() {
+        if visited.insert(idx) {
+            visit_queue.extend(graph.neighbors_directed(idx, direction));
+        }
+    }
+
+    visited
+}
+
+/// Finds the `max` shortest paths (without cycles) with Yen's algorithm, in order of their length.
+///
+/// Every path after the first one deviates from one of the previous paths at some node (the spur
+/// node), so the candidates for the next path are the shortest ways of deviating from the previous
+/// path at each of its nodes.
+fn shortest_paths(
+    graph: &DepGraph,
+    from: &[NodeIndex<u16>],
+    to: &[NodeIndex<u16>],
+    max: usize,
+) -> Vec<Vec<NodeIndex<u16>>> {
+    let no_nodes = HashSet::new();
+    let Some(first) = shortest_path(graph, from, to, &no_nodes, &no_nodes) else {
+        return Vec::new();
+    };
+
+    let mut paths = vec![first];
+    let mut candidates: Vec<Vec<NodeIndex<u16>>> = Vec::new();
+    while paths.len() < max {
+        let previous = paths.last().unwrap();
+        let mut spur_paths = Vec::new();
+
+        // Deviating in the first node, if there are multiple `from` packages
+        let starts: Vec<_> =
+            from.iter().copied().filter(|&s| !paths.iter().any(|p| p[0] == s)).collect();
+        spur_paths.extend(shortest_path(graph, &starts, to, &no_nodes, &no_nodes));
+
+        for (spur_pos, &spur) in previous[..previous.len() - 1].iter().enumerate() {
+            let root = &previous[..spur_pos];
+            // Don't take the same next step as any of the paths found so far that share the root
+            let taken: HashSet<_> = paths
+                .iter()
+                .filter(|p| p.starts_with(&previous[..=spur_pos]))
+                .filter_map(|p| p.get(spur_pos + 1).copied())
+                .collect();
+            let removed: HashSet<_> = root.iter().copied().collect();
+
+            let spur_path = shortest_path(graph, &[spur], to, &removed, &taken);
+            spur_paths.extend(spur_path.map(|p| root.iter().copied().chain(p).collect()));
+        }
+
+        for path in spur_paths {
+            if !paths.contains(&path) && !candidates.contains(&path) {
+                candidates.push(path);
+            }
+        }
+
+        // The shortest candidate is the next path, the first one found if there are several
+        let Some((next, _)) = candidates.iter().enumerate().min_by_key(|(_, p)| p.len()) else {
+            break;
+        };
+        paths.push(candidates.remove(next));
+    }
+
+    paths
+}
+
+/// Breadth-first search for the shortest path with at least one edge from one of the `from`
+/// packages to one of the `to` packages, without visiting `removed` nodes. The first step can't go
+/// to any of the `skip_first` nodes.
+fn shortest_path(
+    graph: &DepGraph,
+    from: &[NodeIndex<u16>],
+    to: &[NodeIndex<u16>],
+    removed: &HashSet<NodeIndex<u16>>,
+    skip_first: &HashSet<NodeIndex<u16>>,
+) -> Option<Vec<NodeIndex<u16>>> {
+    let mut parents = HashMap::new();
+    let mut queue: VecDeque<_> = from.iter().copied().filter(|s| !removed.contains(s)).collect();
+
+    while let Some(idx) = queue.pop_front() {
+        for next in graph.neighbors_directed(idx, Direction::Outgoing) {
+            if removed.contains(&next)
+                || from.contains(&next)
+                || (from.contains(&idx) && skip_first.contains(&next))
+                || parents.contains_key(&next)
+            {
+                continue;
+            }
+            parents.insert(next, idx);
+
+            if to.contains(&next) {
+                let mut path = vec![next];
+                while let Some(&parent) = parents.get(path.last().unwrap()) {
+                    path.push(parent);
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            queue.push_back(next);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::parse_view_options, dep_info::DepKind, graph::test_graph};
+
+    fn graph(deps: &[(&str, &str)]) -> DepGraph {
+        let deps: Vec<_> = deps.iter().map(|&(from, to)| (from, to, DepKind::NORMAL)).collect();
+        test_graph(&["app"], &deps)
+    }
+
+    fn select(graph: &mut DepGraph, from: &str, to: &str, max_paths: Option<usize>) {
+        select_paths(graph, &[from.to_owned()], &[to.to_owned()], max_paths, false).unwrap();
+    }
+
+    fn edges(graph: &DepGraph) -> Vec<(String, String)> {
+        let mut edges: Vec<_> = graph
+            .edge_references()
+            .map(|e| (graph[e.source()].name.clone(), graph[e.target()].name.clone()))
+            .collect();
+        edges.sort();
+        edges
+    }
+
+    fn edge(from: &str, to: &str) -> (String, String) {
+        (from.to_owned(), to.to_owned())
+    }
+
+    /// app -> a | b -> mid -> c -> leaf, and a longer way app -> d -> e -> f -> leaf
+    fn diamond() -> DepGraph {
+        graph(&[
+            ("app", "a"),
+            ("app", "b"),
+            ("a", "mid"),
+            ("b", "mid"),
+            ("mid", "leaf"),
+            ("app", "d"),
+            ("d", "e"),
+            ("e", "f"),
+            ("f", "leaf"),
+            ("app", "unrelated"),
+            ("leaf", "below"),
+        ])
+    }
+
+    #[test]
+    fn select_all_paths() {
+        let mut graph = diamond();
+        select(&mut graph, "app", "leaf", None);
+
+        assert_eq!(graph.node_count(), 8);
+        assert_eq!(
+            edges(&graph),
+            [
+                edge("a", "mid"),
+                edge("app", "a"),
+                edge("app", "b"),
+                edge("app", "d"),
+                edge("b", "mid"),
+                edge("d", "e"),
+                edge("e", "f"),
+                edge("f", "leaf"),
+                edge("mid", "leaf"),
+            ]
+        );
+        assert!(graph.node_weights().all(|pkg| pkg.dep_info.is_on_path));
+    }
+
+    #[test]
+    fn select_shortest_paths() {
+        let mut graph = diamond();
+        select(&mut graph, "app", "leaf", Some(2));
+        assert_eq!(
+            edges(&graph),
+            [
+                edge("a", "mid"),
+                edge("app", "a"),
+                edge("app", "b"),
+                edge("b", "mid"),
+                edge("mid", "leaf")
+            ]
+        );
+
+        let mut graph = diamond();
+        select(&mut graph, "app", "leaf", Some(3));
+        assert_eq!(graph.node_count(), 8);
+
+        let mut graph = diamond();
+        select(&mut graph, "app", "leaf", Some(1));
+        assert_eq!(edges(&graph).len(), 3);
+    }
+
+    #[test]
+    fn select_paths_highlights_instead_of_removing() {
+        let mut graph = diamond();
+        let (from, to) = (["app".to_owned()], ["mid".to_owned()]);
+        select_paths(&mut graph, &from, &to, None, true).unwrap();
+
+        assert_eq!(graph.node_count(), 10);
+        let on_path: HashSet<_> = graph
+            .node_weights()
+            .filter(|pkg| pkg.dep_info.is_on_path)
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+        assert_eq!(on_path, HashSet::from(["app", "a", "b", "mid"]));
+    }
+
+    #[test]
+    fn select_paths_without_cycles() {
+        // Cycles can only exist through dev-dependencies of workspace members
+        let mut graph = test_graph(
+            &["app", "b"],
+            &[
+                ("app", "a", DepKind::NORMAL),
+                ("a", "b", DepKind::NORMAL),
+                ("b", "app", DepKind::DEV),
+                ("b", "leaf", DepKind::NORMAL),
+            ],
+        );
+        let from = graph.node_indices().find(|&idx| graph[idx].name == "app").unwrap();
+        let to = graph.node_indices().find(|&idx| graph[idx].name == "leaf").unwrap();
+
+        let paths = shortest_paths(&graph, &[from], &[to], 10);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].len(), 4);
+
+        let mut limited = graph.clone();
+        select(&mut limited, "app", "leaf", Some(10));
+        assert_eq!(edges(&limited), [edge("a", "b"), edge("app", "a"), edge("b", "leaf")]);
+
+        select(&mut graph, "app", "leaf", None);
+        assert_eq!(edges(&graph), [edge("a", "b"), edge("app", "a"), edge("b", "leaf")]);
+    }
+
+    #[test]
+    fn select_paths_through_many_diamonds() {
+        // 2^40 paths, which can't be enumerated
+        let names: Vec<_> =
+            (0..=40).map(|i| [format!("a{i}"), format!("b{i}"), format!("n{i}")]).collect();
+        let mut deps = vec![("app", "a1"), ("app", "b1")];
+        for i in 1..=40 {
+            let [a, b, n] = &names[i];
+            deps.extend([(a.as_str(), n.as_str()), (b.as_str(), n.as_str())]);
+            if let Some([next_a, next_b, _]) = names.get(i + 1) {
+                deps.extend([(n.as_str(), next_a.as_str()), (n.as_str(), next_b.as_str())]);
+            }
+        }
+
+        let mut graph = graph(&deps);
+        select(&mut graph, "app", "n40", None);
+        assert_eq!(graph.edge_count(), 40 * 4);
+
+        let mut graph = self::graph(&deps);
+        select(&mut graph, "app", "n40", Some(4));
+        // All paths have the same length, the first path and the ones that deviate from it in
+        // one of the first three diamonds are taken
+        assert_eq!(graph.edge_count(), 40 * 2 + 3 * 2);
+    }
+
+    #[test]
+    fn max_paths_must_be_positive() {
+        let args = |n: &str| {
+            ["--path-from", "app", "--path-to", "log", "--max-paths", n].map(ToOwned::to_owned)
+        };
+        assert!(parse_view_options(&args("0")).is_err());
+        assert_eq!(parse_view_options(&args("1")).unwrap().max_paths, Some(1));
+    }
+}
@@ -0,0 +1,179 @@
+use std::collections::HashSet;
+
+use anyhow::Context;
+use cargo_metadata::{DependencyKind as MetaDepKind, Metadata, semver::Version};
+use serde_json::json;
+
+mod auditable;
+mod lockfile;
+mod sbom;
+
+pub(crate) use self::{
+    auditable::metadata_from_binary, lockfile::metadata_from_lockfile, sbom::metadata_from_sbom,
+};
+
+/// The `cargo metadata` representation of crates.io as a package source.
+const CRATES_IO_SOURCE: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+/// A package read from somewhere other than `cargo metadata`, with the information that is needed
+/// to build the dependency graph.
+#[derive(Debug)]
+struct ImportedPackage {
+    name: String,
+    version: String,
+    /// the package's source as `cargo metadata` would report it, `None` for local paths
+    source: Option<String>,
+    /// whether this is one of the packages the input describes, which are treated like workspace
+    /// members
+    is_root: bool,
+    is_proc_macro: bool,
+    dependencies: Vec<ImportedDependency>,
+}
+
+#[derive(Debug)]
+struct ImportedDependency {
+    /// the index of the dependency in the list of imported packages
+    package: usize,
+    kind: MetaDepKind,
+    optional: bool,
+}
+
+/// Creates the same data `cargo metadata` would return for the imported packages, so the graph
+/// can be built (and filtered) the same way as for a Cargo workspace.
+///
+/// If none of the packages is marked as a root, the ones nothing depends on are used instead.
+fn into_metadata(packages: &[ImportedPackage]) -> anyhow::Result<Metadata> {
+    // Package names and versions don't need to be unique, so the index is added
+    let ids: Vec<_> = packages
+        .iter()
+        .enumerate()
+        .map(|(i, pkg)| format!("{} {} #{i}", pkg.name, pkg.version))
+        .collect();
+
+    let dependencies: HashSet<_> =
+        packages.iter().flat_map(|pkg| &pkg.dependencies).map(|dep| dep.package).collect();
+    let has_roots = packages.iter().any(|pkg| pkg.is_root);
+    let workspace_members: Vec<_> = (0..packages.len())
+        .filter(|&i| if has_roots { packages[i].is_root } else { !dependencies.contains(&i) })
+        .map(|i| &ids[i])
+        .collect();
+
+    let mut meta_packages = Vec::new();
+    let mut nodes = Vec::new();
+    for (pkg, id) in packages.iter().zip(&ids) {
+        Version::parse(&pkg.version)
+            .with_context(|| format!("invalid version `{}` of `{}`", pkg.version, pkg.name))?;
+
+        let target_kind = if pkg.is_proc_macro { "proc-macro" } else { "lib" };
+        let meta_dependencies: Vec<_> = pkg
+            .dependencies
+            .iter()
+            .map(|dep| {
+                json!({
+                    "name": packages[dep.package].name,
+                    "source": packages[dep.package].source,
+                    "req": "*",
+                    "kind": dep.kind,
+                    "optional": dep.optional,
+                    "uses_default_features": true,
+                    "features": [],
+                })
+            })
+            .collect();
+        meta_packages.push(json!({
+            "name": pkg.name,
+            "version": pkg.version,
+            "id": id,
+            "source": pkg.source,
+            "dependencies": meta_dependencies,
+            "targets": [{
+                "name": pkg.name,
+                "kind": [target_kind],
+                "crate_types": [target_kind],
+                "src_path": "",
+            }],
+            "features": {},
+            "manifest_path": "",
+        }));
+
+        let deps: Vec<_> = pkg
+            .dependencies
+            .iter()
+            .map(|dep| {
+                json!({
+                    "name": packages[dep.package].name,
+                    "pkg": ids[dep.package],
+                    "dep_kinds": [{ "kind": dep.kind }],
+                })
+            })
+            .collect();
+        let dependency_ids: Vec<_> = pkg.dependencies.iter().map(|dep| &ids[dep.package]).collect();
+        nodes.push(json!({ "id": id, "deps": deps, "dependencies": dependency_ids }));
+    }
+
+    let metadata = json!({
+        "packages": meta_packages,
+        "workspace_members": workspace_members,
+        "resolve": { "nodes": nodes, "root": null },
+        "workspace_root": "",
+        "target_directory": "",
+        "version": 1,
+    });
+    Ok(serde_json::from_value(metadata)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{cli::parse_view_options, graph::get_dep_graph};
+
+    fn package(name: &str, version: &str, source: Option<&str>) -> ImportedPackage {
+        ImportedPackage {
+            name: name.to_owned(),
+            version: version.to_owned(),
+            source: source.map(ToOwned::to_owned),
+            is_root: false,
+            is_proc_macro: false,
+            dependencies: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn packages_with_the_same_name_and_version_stay_apart() {
+        let mut app = package("app", "0.1.0", None);
+        app.dependencies = (1..5)
+            .map(|package| ImportedDependency {
+                package,
+                kind: MetaDepKind::Normal,
+                optional: false,
+            })
+            .collect();
+        let packages = [
+            app,
+            package("util", "0.1.0", None),
+            package("util", "0.1.0", None),
+            package("log", "0.4.0", Some(CRATES_IO_SOURCE)),
+            package("log", "0.4.0", Some("git+https://github.com/rust-lang/log#0123abc")),
+        ];
+
+        let metadata = into_metadata(&packages).unwrap();
+        let ids: HashSet<_> = metadata.packages.iter().map(|pkg| &pkg.id).collect();
+        assert_eq!(ids.len(), 5);
+        // Without any roots, the packages nothing depends on are the workspace members
+        assert_eq!(metadata.workspace_members, [metadata.packages[0].id.clone()]);
+
+        let config = parse_view_options(&[]).unwrap();
+        let graph = get_dep_graph(metadata, &HashMap::new(), &config).unwrap();
+        assert_eq!(graph.node_count(), 5);
+        assert_eq!(graph.edge_count(), 4);
+    }
+
+    #[test]
+    fn invalid_version() {
+        let packages = [package("app", "1.0", None)];
+        let err = into_metadata(&packages).unwrap_err();
+        assert_eq!(err.to_string(), "invalid version `1.0` of `app`");
+    }
+}
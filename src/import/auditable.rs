@@ -0,0 +1,164 @@
+use std::fs;
+
+use anyhow::{Context, anyhow};
+use cargo_metadata::{DependencyKind as MetaDepKind, Metadata};
+use object::{Object, ObjectSection};
+use serde::Deserialize;
+
+use super::{CRATES_IO_SOURCE, ImportedDependency, ImportedPackage, into_metadata};
+
+/// The name of the section `cargo auditable` embeds the dependency information in.
+const SECTION_NAME: &str = ".dep-v0";
+
+/// Decompressing more than this is assumed to be an error (or malicious).
+const MAX_DECOMPRESSED_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct VersionInfo {
+    packages: Vec<AuditablePackage>,
+}
+
+#[derive(Deserialize)]
+struct AuditablePackage {
+    name: String,
+    version: String,
+    source: String,
+    #[serde(default)]
+    kind: AuditableKind,
+    #[serde(default)]
+    dependencies: Vec<usize>,
+    #[serde(default)]
+    root: bool,
+}
+
+#[derive(Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum AuditableKind {
+    /// only used at build time, i.e. a build-dependency or proc-macro
+    Build,
+    #[default]
+    Runtime,
+}
+
+/// Reads the dependency tree that `cargo auditable` embedded into a compiled binary.
+///
+/// The embedded information only distinguishes runtime and build-time dependencies, doesn't say
+/// which packages are proc-macros and only which kind of source (like git) a package came from.
+pub(crate) fn metadata_from_binary(path: &str) -> anyhow::Result<Metadata> {
+    let data = fs::read(path).with_context(|| format!("failed to read `{path}`"))?;
+    let file = object::File::parse(&*data).with_context(|| format!("failed to parse `{path}`"))?;
+    let section = file.section_by_name(SECTION_NAME).with_context(|| {
+        format!("`{path}` contains no dependency information, was it built with `cargo auditable`?")
+    })?;
+
+    metadata_from_section(section.data()?)
+}
+
+/// Reads the compressed dependency information from the `.dep-v0` section.
+fn metadata_from_section(compressed: &[u8]) -> anyhow::Result<Metadata> {
+    let json =
+        miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(compressed, MAX_DECOMPRESSED_SIZE)
+            .map_err(|e| anyhow!("failed to decompress the dependency information: {e}"))?;
+    let info: VersionInfo =
+        serde_json::from_slice(&json).context("failed to parse the dependency information")?;
+
+    let packages: Vec<_> = info
+        .packages
+        .iter()
+        .map(|pkg| {
+            let dependencies = pkg
+                .dependencies
+                .iter()
+                .map(|&idx| {
+                    let dep = info.packages.get(idx).context("invalid dependency index")?;
+                    let kind = match dep.kind {
+                        AuditableKind::Build => MetaDepKind::Build,
+                        AuditableKind::Runtime => MetaDepKind::Normal,
+                    };
+                    Ok(ImportedDependency { package: idx, kind, optional: false })
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            let source = match pkg.source.as_str() {
+                "crates.io" => Some(CRATES_IO_SOURCE.to_owned()),
+                "local" | "builtin" => None,
+                // Only the kind of source is known, not its URL
+                source => Some(format!("{source}+unknown")),
+            };
+
+            Ok(ImportedPackage {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                source,
+                is_root: pkg.root,
+                is_proc_macro: false,
+                dependencies,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    into_metadata(&packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use miniz_oxide::deflate::compress_to_vec_zlib;
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    fn section(info: &Value) -> Vec<u8> {
+        compress_to_vec_zlib(info.to_string().as_bytes(), 6)
+    }
+
+    #[test]
+    fn reads_packages_and_dependencies() {
+        let info = json!({
+            "packages": [
+                { "name": "app", "version": "0.1.0", "source": "local", "dependencies": [1, 2], "root": true },
+                { "name": "log", "version": "0.4.0", "source": "crates.io" },
+                { "name": "cc", "version": "1.0.0", "source": "git", "kind": "build" },
+            ],
+        });
+        let metadata = metadata_from_section(&section(&info)).unwrap();
+
+        assert_eq!(metadata.workspace_members.len(), 1);
+        let app = &metadata[&metadata.workspace_members[0]];
+        assert_eq!(app.name, "app");
+        assert_eq!(app.source, None);
+
+        let deps: Vec<_> = app.dependencies.iter().map(|dep| (&*dep.name, dep.kind)).collect();
+        assert_eq!(deps, [("log", MetaDepKind::Normal), ("cc", MetaDepKind::Build)]);
+
+        let sources: Vec<_> =
+            metadata.packages.iter().map(|pkg| pkg.source.as_ref().map(|s| &*s.repr)).collect();
+        assert_eq!(sources, [None, Some(CRATES_IO_SOURCE), Some("git+unknown")]);
+    }
+
+    #[test]
+    fn invalid_dependency_index() {
+        let info = json!({
+            "packages": [
+                { "name": "app", "version": "0.1.0", "source": "local", "dependencies": [1], "root": true },
+            ],
+        });
+        let err = metadata_from_section(&section(&info)).unwrap_err();
+        assert_eq!(err.to_string(), "invalid dependency index");
+    }
+
+    #[test]
+    fn invalid_compressed_data() {
+        let err = metadata_from_section(b"not zlib").unwrap_err();
+        assert!(err.to_string().starts_with("failed to decompress"), "{err}");
+    }
+
+    #[test]
+    fn missing_section() {
+        // The test binary itself isn't built with `cargo auditable`
+        let path = env::current_exe().unwrap();
+        let err = metadata_from_binary(path.to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().contains("contains no dependency information"), "{err}");
+    }
+}
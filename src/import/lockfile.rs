@@ -0,0 +1,187 @@
+use std::fs;
+
+use anyhow::{Context, bail};
+use cargo_metadata::{DependencyKind as MetaDepKind, Metadata};
+use serde::Deserialize;
+
+use super::{ImportedDependency, ImportedPackage, into_metadata};
+
+#[derive(Deserialize)]
+struct Lockfile {
+    #[serde(default, rename = "package")]
+    packages: Vec<LockedPackage>,
+}
+
+#[derive(Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+    /// `name`, `name version` or `name version (source)`, with as little as needed to uniquely
+    /// identify the dependency
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// Reads the packages and their dependencies from a `Cargo.lock` file, without running `cargo`.
+///
+/// The lockfile doesn't say which packages are workspace members, so all packages from local paths
+/// are treated as such. It also doesn't say of which kind a dependency is, so all dependencies
+/// have an unknown kind.
+pub(crate) fn metadata_from_lockfile(path: &str) -> anyhow::Result<Metadata> {
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read `{path}`"))?;
+    read_lockfile(&text, path)
+}
+
+fn read_lockfile(text: &str, path: &str) -> anyhow::Result<Metadata> {
+    let lockfile: Lockfile =
+        toml::from_str(text).with_context(|| format!("failed to parse `{path}`"))?;
+
+    let packages: Vec<_> = lockfile
+        .packages
+        .iter()
+        .map(|pkg| {
+            let dependencies = pkg
+                .dependencies
+                .iter()
+                .map(|dep| {
+                    let idx = find_package(&lockfile.packages, dep).with_context(|| {
+                        format!("invalid dependency of `{}` in `{path}`", pkg.name)
+                    })?;
+                    Ok(ImportedDependency {
+                        package: idx,
+                        kind: MetaDepKind::Unknown,
+                        optional: false,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+
+            Ok(ImportedPackage {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                source: pkg.source.clone(),
+                is_root: pkg.source.is_none(),
+                is_proc_macro: false,
+                dependencies,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    into_metadata(&packages)
+}
+
+/// Finds the package a dependency entry refers to. Cargo only adds the version and source to the
+/// entry if they are needed to tell packages with the same name apart, so an entry matching several
+/// packages means the lockfile is invalid.
+fn find_package(packages: &[LockedPackage], dep: &str) -> anyhow::Result<usize> {
+    let mut parts = dep.splitn(3, ' ');
+    let name = parts.next().unwrap_or_default();
+    let version = parts.next();
+    let source = parts.next().and_then(|s| s.strip_prefix('(')?.strip_suffix(')'));
+
+    let mut matches = packages.iter().enumerate().filter(|(_, pkg)| {
+        pkg.name == name
+            && version.is_none_or(|v| pkg.version == v)
+            && source.is_none_or(|s| pkg.source.as_deref() == Some(s))
+    });
+    match (matches.next(), matches.next()) {
+        (Some((idx, _)), None) => Ok(idx),
+        (None, _) => bail!("`{dep}` not found"),
+        (Some(_), Some(_)) => bail!("`{dep}` matches several packages"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A lockfile with two versions of `log` and two `log 0.4.20` packages from different sources,
+    /// referred to as cargo writes them
+    const LOCKFILE: &str = r#"
+version = 4
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "cfg-if",
+ "log 0.3.9",
+ "log 0.4.20 (git+https://github.com/rust-lang/log#0123abc)",
+ "log 0.4.20 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "cfg-if"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "log"
+version = "0.3.9"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "log 0.4.20 (registry+https://github.com/rust-lang/crates.io-index)",
+]
+
+[[package]]
+name = "log"
+version = "0.4.20"
+source = "git+https://github.com/rust-lang/log#0123abc"
+dependencies = [
+ "cfg-if",
+]
+
+[[package]]
+name = "log"
+version = "0.4.20"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    fn packages() -> Vec<LockedPackage> {
+        toml::from_str::<Lockfile>(LOCKFILE).unwrap().packages
+    }
+
+    #[test]
+    fn dependency_entry_forms() {
+        let packages = packages();
+        assert_eq!(find_package(&packages, "cfg-if").unwrap(), 1);
+        assert_eq!(find_package(&packages, "log 0.3.9").unwrap(), 2);
+        let git = "log 0.4.20 (git+https://github.com/rust-lang/log#0123abc)";
+        assert_eq!(find_package(&packages, git).unwrap(), 3);
+        let registry = "log 0.4.20 (registry+https://github.com/rust-lang/crates.io-index)";
+        assert_eq!(find_package(&packages, registry).unwrap(), 4);
+    }
+
+    #[test]
+    fn ambiguous_dependency_entries() {
+        let packages = packages();
+        let err = find_package(&packages, "log").unwrap_err();
+        assert_eq!(err.to_string(), "`log` matches several packages");
+        let err = find_package(&packages, "log 0.4.20").unwrap_err();
+        assert_eq!(err.to_string(), "`log 0.4.20` matches several packages");
+    }
+
+    #[test]
+    fn reads_all_packages_and_dependencies() {
+        let metadata = read_lockfile(LOCKFILE, "Cargo.lock").unwrap();
+        assert_eq!(metadata.packages.len(), 5);
+        assert_eq!(metadata.workspace_members, [metadata.packages[0].id.clone()]);
+
+        let resolve = metadata.resolve.as_ref().unwrap();
+        let deps: Vec<_> = resolve.nodes.iter().map(|node| node.deps.len()).collect();
+        assert_eq!(deps, [4, 0, 1, 1, 0]);
+        assert!(
+            resolve.nodes[0].deps.iter().all(|dep| dep.dep_kinds[0].kind == MetaDepKind::Unknown)
+        );
+    }
+
+    #[test]
+    fn missing_dependency() {
+        let lockfile = LOCKFILE.replace(" \"cfg-if\",\n]", " \"cfg-of\",\n]");
+        let err = read_lockfile(&lockfile, "Cargo.lock").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "invalid dependency of `log` in `Cargo.lock`: `cfg-of` not found"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::HashMap, fs};
+
+use anyhow::{Context, bail};
+use cargo_metadata::{DependencyKind as MetaDepKind, Metadata};
+use serde_json::Value;
+
+use super::{CRATES_IO_SOURCE, ImportedDependency, ImportedPackage, into_metadata};
+
+/// Reads the packages and their dependencies from a CycloneDX or SPDX SBOM in JSON format.
+pub(crate) fn metadata_from_sbom(path: &str) -> anyhow::Result<Metadata> {
+    let text = fs::read_to_string(path).with_context(|| format!("failed to read `{path}`"))?;
+    let sbom: Value =
+        serde_json::from_str(&text).with_context(|| format!("failed to parse `{path}`"))?;
+
+    let packages = if sbom["bomFormat"] == "CycloneDX" {
+        read_cyclonedx(&sbom)?
+    } else if sbom["spdxVersion"].is_string() {
+        read_spdx(&sbom)?
+    } else {
+        bail!("`{path}` is neither a CycloneDX nor an SPDX SBOM in JSON format");
+    };
+
+    into_metadata(&packages)
+}
+
+/// Reads a CycloneDX SBOM. The component described by the SBOM is the root, and the dependency
+/// kinds are derived from the dependencies' scope: `excluded` components are only used for
+/// building or testing, but it isn't known which one.
+fn read_cyclonedx(sbom: &Value) -> anyhow::Result<Vec<ImportedPackage>> {
+    let root = &sbom["metadata"]["component"];
+    let components = root.is_object().then_some(root).into_iter().chain(array(&sbom["components"]));
+
+    let mut packages = Vec::new();
+    let mut indices = HashMap::new();
+    let mut scopes = Vec::new();
+    for (i, component) in components.enumerate() {
+        if let Some(bom_ref) = component["bom-ref"].as_str() {
+            indices.insert(bom_ref, i);
+        }
+        scopes.push(component["scope"].as_str());
+
+        let is_proc_macro = array(&component["properties"]).any(|property| {
+            property["name"] == "cargo-depgraph:is_proc_macro" && property["value"] == "true"
+        });
+        packages.push(ImportedPackage {
+            name: string(component, "name")?,
+            version: string(component, "version")?,
+            source: component["purl"].as_str().and_then(source_from_purl),
+            is_root: i == 0 && root.is_object(),
+            is_proc_macro,
+            dependencies: Vec::new(),
+        });
+    }
+
+    for dependency in array(&sbom["dependencies"]) {
+        let Some(&idx) = dependency["ref"].as_str().and_then(|r| indices.get(r)) else {
+            continue;
+        };
+        for dep_ref in array(&dependency["dependsOn"]).filter_map(Value::as_str) {
+            let Some(&dep_idx) = indices.get(dep_ref) else {
+                continue;
+            };
+            let kind = match scopes[dep_idx] {
+                Some("excluded") => MetaDepKind::Unknown,
+                _ => MetaDepKind::Normal,
+            };
+            let optional = scopes[dep_idx] == Some("optional");
+            packages[idx].dependencies.push(ImportedDependency {
+                package: dep_idx,
+                kind,
+                optional,
+            });
+        }
+    }
+
+    Ok(packages)
+}
+
+/// Reads an SPDX document. The packages the document describes are the roots, and the dependency
+/// kinds are derived from the relationship types.
+fn read_spdx(sbom: &Value) -> anyhow::Result<Vec<ImportedPackage>> {
+    let mut packages = Vec::new();
+    let mut indices = HashMap::new();
+    for (i, package) in array(&sbom["packages"]).enumerate() {
+        indices.insert(string(package, "SPDXID")?, i);
+
+        let purl = array(&package["externalRefs"])
+            .find(|r| r["referenceType"] == "purl")
+            .and_then(|r| r["referenceLocator"].as_str());
+        packages.push(ImportedPackage {
+            name: string(package, "name")?,
+            version: string(package, "versionInfo")?,
+            source: purl.and_then(source_from_purl),
+            is_root: false,
+            is_proc_macro: false,
+            dependencies: Vec::new(),
+        });
+    }
+
+    // Older documents list the described packages here instead of using relationships
+    for id in array(&sbom["documentDescribes"]).filter_map(Value::as_str) {
+        if let Some(&idx) = indices.get(id) {
+            packages[idx].is_root = true;
+        }
+    }
+
+    for relationship in array(&sbom["relationships"]) {
+        let element = relationship["spdxElementId"].as_str().unwrap_or_default();
+        let related = relationship["relatedSpdxElement"].as_str().unwrap_or_default();
+        let ty = relationship["relationshipType"].as_str().unwrap_or_default();
+
+        if ty == "DESCRIBES" {
+            if let Some(&idx) = indices.get(related) {
+                packages[idx].is_root = true;
+            }
+            continue;
+        }
+
+        // The dependent, the dependency and how it is used
+        let (dependent, dependency, kind, optional) = match ty {
+            "DEPENDS_ON" => (element, related, MetaDepKind::Normal, false),
+            "DEPENDENCY_OF" | "RUNTIME_DEPENDENCY_OF" => {
+                (related, element, MetaDepKind::Normal, false)
+            }
+            "OPTIONAL_DEPENDENCY_OF" => (related, element, MetaDepKind::Normal, true),
+            "BUILD_DEPENDENCY_OF" => (related, element, MetaDepKind::Build, false),
+            "DEV_DEPENDENCY_OF" | "TEST_DEPENDENCY_OF" => {
+                (related, element, MetaDepKind::Development, false)
+            }
+            _ => continue,
+        };
+        let (Some(&idx), Some(&dep_idx)) = (indices.get(dependent), indices.get(dependency)) else {
+            continue;
+        };
+        packages[idx].dependencies.push(ImportedDependency { package: dep_idx, kind, optional });
+    }
+
+    Ok(packages)
+}
+
+/// The source of a package as `cargo metadata` would report it, from its package URL.
+/// Packages that aren't Rust crates are treated like local paths.
+fn source_from_purl(purl: &str) -> Option<String> {
+    let rest = purl.strip_prefix("pkg:cargo/")?;
+    let qualifiers = rest.split_once('?').map_or("", |(_, qualifiers)| qualifiers);
+    let qualifiers = qualifiers.split('#').next().unwrap_or_default();
+
+    let mut source = Some(CRATES_IO_SOURCE.to_owned());
+    for qualifier in qualifiers.split('&') {
+        match qualifier.split_once('=') {
+            Some(("repository_url", url)) => source = Some(format!("registry+{}", decode(url))),
+            Some(("vcs_url", url)) => {
+                let url = decode(url);
+                let url = url.strip_prefix("git+").unwrap_or(&url);
+                // The commit is appended with `@`, `cargo metadata` appends it with `#`
+                let url = match url.rsplit_once('@') {
+                    Some((url, commit)) if !commit.contains('/') => format!("{url}#{commit}"),
+                    _ => url.to_owned(),
+                };
+                source = Some(format!("git+{url}"));
+            }
+            _ => {}
+        }
+    }
+
+    source
+}
+
+/// Decodes a percent-encoded purl component.
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let hex =
+            tail.get(..2).and_then(|hex| u8::from_str_radix(str::from_utf8(hex).ok()?, 16).ok());
+        match hex {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+fn array(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn string(value: &Value, key: &str) -> anyhow::Result<String> {
+    value[key]
+        .as_str()
+        .map(ToOwned::to_owned)
+        .with_context(|| format!("missing `{key}` in {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        cli::{Config, parse_view_options},
+        dep_info::DepKind,
+        graph::{DepGraph, get_dep_graph, test_graph, update_dep_info},
+        output::write_output,
+        package::PackageSource,
+    };
+
+    fn config(args: &[&str]) -> Config {
+        let args: Vec<_> = args.iter().map(|&arg| arg.to_owned()).collect();
+        parse_view_options(&args).unwrap()
+    }
+
+    /// app depends on log, which depends on cfg-if from git, and on cc as a build-dependency
+    fn graph() -> DepGraph {
+        let mut graph = test_graph(
+            &["app"],
+            &[
+                ("app", "log", DepKind::NORMAL),
+                ("app", "cc", DepKind::BUILD),
+                ("log", "cfg-if", DepKind::NORMAL),
+            ],
+        );
+        let cfg_if = graph.node_weights_mut().find(|pkg| pkg.name == "cfg-if").unwrap();
+        cfg_if.source =
+            PackageSource::Git("https://github.com/rust-lang/cfg-if#0123abc".to_owned());
+        cfg_if.id = "cfg-if@0.1.0 (git+https://github.com/rust-lang/cfg-if#0123abc)".to_owned();
+        graph
+    }
+
+    /// Writes the graph in the given format and builds a graph from the result again.
+    fn round_trip(format: &str) -> DepGraph {
+        let mut out = Vec::new();
+        write_output(&mut out, &graph(), &config(&["--output-format", format])).unwrap();
+        let sbom: Value = serde_json::from_slice(&out).unwrap();
+        let packages = match format {
+            "cyclonedx" => read_cyclonedx(&sbom).unwrap(),
+            _ => read_spdx(&sbom).unwrap(),
+        };
+
+        let metadata = into_metadata(&packages).unwrap();
+        let mut graph = get_dep_graph(metadata, &HashMap::new(), &config(&["--all-deps"])).unwrap();
+        update_dep_info(&mut graph);
+        graph
+    }
+
+    /// The packages with their sources, and the dependencies with their kinds, sorted.
+    fn contents(graph: &DepGraph) -> (Vec<String>, Vec<String>) {
+        let mut packages: Vec<_> = graph
+            .node_weights()
+            .map(|pkg| {
+                format!("{} {} {:?} {}", pkg.name, pkg.version, pkg.source, pkg.is_ws_member)
+            })
+            .collect();
+        packages.sort();
+
+        let mut edges: Vec<_> = graph
+            .edge_indices()
+            .map(|edge| {
+                let (source, target) = graph.edge_endpoints(edge).unwrap();
+                let kind = graph[edge].info.kind;
+                format!("{} -> {}: {}", graph[source].name, graph[target].name, kind.as_str())
+            })
+            .collect();
+        edges.sort();
+
+        (packages, edges)
+    }
+
+    #[test]
+    fn cyclonedx_round_trip() {
+        let (packages, edges) = contents(&round_trip("cyclonedx"));
+        assert_eq!(packages, contents(&graph()).0);
+        // CycloneDX doesn't tell build- and dev-dependencies apart
+        assert_eq!(edges, ["app -> cc: unknown", "app -> log: normal", "log -> cfg-if: normal"]);
+    }
+
+    #[test]
+    fn spdx_round_trip() {
+        let (packages, edges) = contents(&round_trip("spdx"));
+        assert_eq!(packages, contents(&graph()).0);
+        assert_eq!(edges, contents(&graph()).1);
+    }
+
+    #[test]
+    fn purl_sources() {
+        assert_eq!(source_from_purl("pkg:cargo/log@0.4.0").as_deref(), Some(CRATES_IO_SOURCE));
+        assert_eq!(
+            source_from_purl("pkg:cargo/a@1.0.0?repository_url=https%3A%2F%2Fexample.com%2Findex")
+                .as_deref(),
+            Some("registry+https://example.com/index"),
+        );
+        assert_eq!(
+            source_from_purl("pkg:cargo/a@1.0.0?vcs_url=git%2Bhttps%3A%2F%2Fexample.com%2Fa%40abc")
+                .as_deref(),
+            Some("git+https://example.com/a#abc"),
+        );
+        assert_eq!(source_from_purl("pkg:npm/a@1.0.0"), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! A simple layered ("Sugiyama-style") graph layout:
+//!
+//! 1. Break dependency cycles by reversing the edges that close them
+//! 2. Assign every package to a layer such that all edges point downwards
+//! 3. Insert dummy items where edges span multiple layers
+//! 4. Reduce edge crossings by repeatedly ordering each layer by the average position of the
+//!    neighbors in the layer above / below
+//!
+//! Coordinates are left to the renderers since they depend on the size of the node labels.
+
+use std::collections::{HashMap, HashSet};
+
+use petgraph::{Direction, stable_graph::NodeIndex, visit::EdgeRef};
+
+use crate::{dep_info::DepInfo, graph::DepGraph};
+
+/// Number of times the layers are reordered (each time top to bottom and then bottom to top).
+const ORDERING_PASSES: usize = 4;
+
+#[derive(Debug)]
+pub(crate) struct Layout {
+    /// the layers from top to bottom, with their items from left to right
+    pub layers: Vec<Vec<LayoutItem>>,
+    pub edges: Vec<LayoutEdge>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum LayoutItem {
+    Node(NodeIndex<u16>),
+    /// a point that an edge passes through
+    Dummy {
+        /// index of the edge in `Layout::edges`
+        edge: usize,
+        layer: usize,
+    },
+}
+
+#[derive(Debug)]
+pub(crate) struct LayoutEdge {
+    pub source: NodeIndex<u16>,
+    pub target: NodeIndex<u16>,
+    /// the combined info of all dependency edges between `source` and `target`
+    pub info: DepInfo,
+    /// whether this edge points upwards because it is part of a dependency cycle
+    pub is_reversed: bool,
+    /// the items this edge passes through, one per layer from top to bottom, including both ends
+    pub items: Vec<LayoutItem>,
+}
+
+impl Layout {
+    pub fn new(graph: &DepGraph) -> Self {
+        let mut nodes: Vec<_> = graph.node_indices().collect();
+        nodes.sort_by(|&a, &b| graph[a].id.cmp(&graph[b].id));
+
+        let reversed = back_edges(graph, &nodes);
+        let node_layers = assign_layers(graph, &nodes, &reversed);
+
+        // Combine parallel edges, orient all edges downwards and insert dummy items
+        let mut edges: Vec<LayoutEdge> = Vec::new();
+        let mut edge_indices: HashMap<_, usize> = HashMap::new();
+        for &idx in &nodes {
+            for edge in graph.edges_directed(idx, Direction::Outgoing) {
+                let info = edge.weight().info;
+                match edge_indices.get(&(edge.source(), edge.target())) {
+                    Some(&i) => edges[i].info.combine_incoming(info),
+                    None => {
+                        edge_indices.insert((edge.source(), edge.target()), edges.len());
+                        edges.push(LayoutEdge {
+                            source: edge.source(),
+                            target: edge.target(),
+                            info,
+                            is_reversed: reversed.contains(&(edge.source(), edge.target())),
+                            items: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let num_layers = node_layers.values().max().map_or(0, |&max| max + 1);
+        let mut layers = vec![Vec::new(); num_layers];
+        for &idx in &nodes {
+            layers[node_layers[&idx]].push(LayoutItem::Node(idx));
+        }
+
+        for (i, edge) in edges.iter_mut().enumerate() {
+            let (upper, lower) = if edge.is_reversed {
+                (edge.target, edge.source)
+            } else {
+                (edge.source, edge.target)
+            };
+
+            edge.items.push(LayoutItem::Node(upper));
+            let (upper_layer, lower_layer) = (node_layers[&upper], node_layers[&lower]);
+            for (layer, items) in
+                layers.iter_mut().enumerate().take(lower_layer).skip(upper_layer + 1)
+            {
+                items.push(LayoutItem::Dummy { edge: i, layer });
+                edge.items.push(LayoutItem::Dummy { edge: i, layer });
+            }
+            edge.items.push(LayoutItem::Node(lower));
+        }
+
+        let mut layout = Self { layers, edges };
+        layout.reduce_crossings();
+        layout
+    }
+
+    fn reduce_crossings(&mut self) {
+        // Neighbors of every item in the layer above and below
+        let mut upper = HashMap::<LayoutItem, Vec<LayoutItem>>::new();
+        let mut lower = HashMap::<LayoutItem, Vec<LayoutItem>>::new();
+        for edge in &self.edges {
+            for pair in edge.items.windows(2) {
+                lower.entry(pair[0]).or_default().push(pair[1]);
+                upper.entry(pair[1]).or_default().push(pair[0]);
+            }
+        }
+
+        for _ in 0..ORDERING_PASSES {
+            for i in 1..self.layers.len() {
+                order_by_neighbors(&mut self.layers, i, i - 1, &upper);
+            }
+            for i in (0..self.layers.len().saturating_sub(1)).rev() {
+                order_by_neighbors(&mut self.layers, i, i + 1, &lower);
+            }
+        }
+    }
+}
+
+/// Finds edges that close a dependency cycle, using a depth-first search starting at the nodes
+/// without incoming edges.
+fn back_edges(
+    graph: &DepGraph,
+    nodes: &[NodeIndex<u16>],
+) -> HashSet<(NodeIndex<u16>, NodeIndex<u16>)> {
+    let mut back_edges = HashSet::new();
+    let mut visited = HashSet::new();
+    let mut on_stack = HashSet::new();
+
+    let roots = nodes
+        .iter()
+        .filter(|&&idx| graph.neighbors_directed(idx, Direction::Incoming).next().is_none());
+    // Nodes that are only part of cycles are not reachable from the roots
+    for &start in roots.chain(nodes) {
+        if !visited.insert(start) {
+            continue;
+        }
+
+        // Stack of nodes along with their not-yet-visited dependencies
+        let mut stack =
+            vec![(start, graph.neighbors_directed(start, Direction::Outgoing).detach())];
+        on_stack.insert(start);
+
+        while let Some((idx, neighbors)) = stack.last_mut() {
+            let idx = *idx;
+            match neighbors.next_node(graph) {
+                Some(next) if on_stack.contains(&next) => {
+                    back_edges.insert((idx, next));
+                }
+                Some(next) => {
+                    if visited.insert(next) {
+                        on_stack.insert(next);
+                        stack.push((
+                            next,
+                            graph.neighbors_directed(next, Direction::Outgoing).detach(),
+                        ));
+                    }
+                }
+                None => {
+                    on_stack.remove(&idx);
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    back_edges
+}
+
+/// Assigns every node the length of the longest path to it from a node without (non-reversed)
+/// incoming edges, so every edge points to a lower layer.
+fn assign_layers(
+    graph: &DepGraph,
+    nodes: &[NodeIndex<u16>],
+    reversed: &HashSet<(NodeIndex<u16>, NodeIndex<u16>)>,
+) -> HashMap<NodeIndex<u16>, usize> {
+    let downwards_edges = |idx: NodeIndex<u16>, direction: Direction| {
+        graph.edges_directed(idx, direction).filter_map(move |edge| {
+            let (source, target) = (edge.source(), edge.target());
+            (!reversed.contains(&(source, target))).then_some(match direction {
+                Direction::Outgoing => target,
+                Direction::Incoming => source,
+            })
+        })
+    };
+
+    // Kahn's algorithm, visiting nodes in topological order
+    let mut in_degrees: HashMap<_, _> =
+        nodes.iter().map(|&idx| (idx, downwards_edges(idx, Direction::Incoming).count())).collect();
+    let mut queue: Vec<_> = nodes.iter().copied().filter(|idx| in_degrees[idx] == 0).collect();
+    let mut layers: HashMap<_, _> = queue.iter().map(|&idx| (idx, 0)).collect();
+
+    while let Some(idx) = queue.pop() {
+        let layer = layers[&idx];
+        for next in downwards_edges(idx, Direction::Outgoing) {
+            let next_layer = layers.entry(next).or_insert(0);
+            *next_layer = (*next_layer).max(layer + 1);
+
+            let in_degree = in_degrees.get_mut(&next).unwrap();
+            *in_degree -= 1;
+            if *in_degree == 0 {
+                queue.push(next);
+            }
+        }
+    }
+
+    layers
+}
+
+/// Sorts the items of `layers[layer]` by the average position of their neighbors in
+/// `layers[other]`. Items without neighbors there keep their position.
+fn order_by_neighbors(
+    layers: &mut [Vec<LayoutItem>],
+    layer: usize,
+    other: usize,
+    neighbors: &HashMap<LayoutItem, Vec<LayoutItem>>,
+) {
+    let positions: HashMap<_, _> =
+        layers[other].iter().enumerate().map(|(pos, &item)| (item, pos as f64)).collect();
+
+    let mut keyed: Vec<_> = layers[layer]
+        .iter()
+        .enumerate()
+        .map(|(pos, &item)| {
+            let neighbor_positions: Vec<_> = neighbors
+                .get(&item)
+                .into_iter()
+                .flatten()
+                .filter_map(|n| positions.get(n))
+                .collect();
+            let key = if neighbor_positions.is_empty() {
+                pos as f64
+            } else {
+                neighbor_positions.iter().copied().sum::<f64>() / neighbor_positions.len() as f64
+            };
+
+            (key, item)
+        })
+        .collect();
+
+    keyed.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    layers[layer] = keyed.into_iter().map(|(_, item)| item).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dep_info::DepKind, graph::test_graph};
+
+    fn node_layers(layout: &Layout, graph: &DepGraph) -> Vec<Vec<String>> {
+        layout
+            .layers
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .map(|item| match item {
+                        LayoutItem::Node(idx) => graph[*idx].name.clone(),
+                        LayoutItem::Dummy { .. } => "-".to_owned(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cycles_are_broken_by_reversing_an_edge() {
+        // `a` dev-depends on the workspace member that depends on it
+        let graph = test_graph(
+            &["cli", "app"],
+            &[
+                ("cli", "app", DepKind::NORMAL),
+                ("app", "a", DepKind::NORMAL),
+                ("a", "app", DepKind::DEV),
+            ],
+        );
+        let layout = Layout::new(&graph);
+
+        assert_eq!(node_layers(&layout, &graph), [["cli"], ["app"], ["a"]]);
+        let reversed: Vec<_> = layout
+            .edges
+            .iter()
+            .filter(|edge| edge.is_reversed)
+            .map(|edge| (graph[edge.source].name.as_str(), graph[edge.target].name.as_str()))
+            .collect();
+        assert_eq!(reversed, [("a", "app")]);
+
+        // The items of every edge go from top to bottom, including the reversed one
+        let layer_of = |item: &LayoutItem| layout.layers.iter().position(|l| l.contains(item));
+        for edge in &layout.edges {
+            let layers: Vec<_> = edge.items.iter().map(layer_of).collect();
+            assert!(layers.windows(2).all(|pair| pair[0] < pair[1]), "{edge:?}");
+        }
+    }
+
+    #[test]
+    fn long_edges_pass_through_dummy_items() {
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "a", DepKind::NORMAL),
+                ("a", "b", DepKind::NORMAL),
+                ("app", "b", DepKind::NORMAL),
+            ],
+        );
+        let layout = Layout::new(&graph);
+
+        assert_eq!(layout.layers.len(), 3);
+        assert_eq!(layout.layers[1].len(), 2);
+        let long_edge = layout.edges.iter().position(|edge| edge.items.len() == 3).unwrap();
+        let edge = &layout.edges[long_edge];
+        assert_eq!(
+            (graph[edge.source].name.as_str(), graph[edge.target].name.as_str()),
+            ("app", "b")
+        );
+        let dummy = LayoutItem::Dummy { edge: long_edge, layer: 1 };
+        assert_eq!(edge.items[1], dummy);
+        assert!(layout.layers[1].contains(&dummy));
+        assert_eq!(layout.edges.iter().filter(|edge| edge.items.len() == 2).count(), 2);
+    }
+}
@@ -1,8 +1,4 @@
-use std::iter;
-
-use cargo_metadata::MetadataCommand;
-
-// `DepInfo` represents the data associated with dependency graph edges
+// `Dependency` and `DepInfo` represent the data associated with dependency graph edges
 mod dep_info;
 // `Package` represents the data associated with dependency graph nodes
 mod package;
@@ -12,69 +8,36 @@ mod graph;
 // Contains some auxiliary logic (currently just checking for packages of the same name)
 mod util;
 
+// Reading the dependency graph from SBOMs, binaries and lockfiles instead of `cargo metadata`
+mod import;
+
+// Layered graph layout for the built-in renderers
+mod layout;
+
+// Caching `cargo metadata` results in the target directory
+mod cache;
 // Command-line parsing
 mod cli;
-// Dot output generation
+// Rendering several views of the graph in one invocation
+mod batch;
+// Output generation (DOT and other formats)
 mod output;
+// Reading the input, building the graph and writing the output, shared by single runs and --batch
+mod pipeline;
 
 use self::{
+    batch::run_batch,
     cli::parse_options,
-    graph::{
-        dedup_transitive_deps, get_dep_graph, remove_deps, remove_irrelevant_deps, update_dep_info,
-    },
-    output::dot,
-    util::set_name_stats,
+    pipeline::{build_graph, load_metadata, write_graph},
 };
 
 fn main() -> anyhow::Result<()> {
     let config = parse_options();
-    let mut cmd = MetadataCommand::new();
-
-    if let Some(path) = &config.manifest_path {
-        cmd.manifest_path(path);
-    }
-
-    let mut other_options = Vec::new();
-    other_options.extend(config.features.iter().flat_map(|f| cli_args("--features", f)));
-    if config.all_features {
-        other_options.push("--all-features".into());
-    }
-    if config.no_default_features {
-        other_options.push("--no-default-features".into());
-    }
-    other_options
-        .extend(config.filter_platform.iter().flat_map(|p| cli_args("--filter-platform", p)));
-    if config.frozen {
-        other_options.push("--frozen".into());
-    }
-    if config.locked {
-        other_options.push("--locked".into());
+    if let Some(path) = &config.batch {
+        return run_batch(path);
     }
-    if config.offline {
-        other_options.push("--offline".into());
-    }
-    other_options.extend(config.unstable_flags.iter().flat_map(|f| cli_args("-Z", f)));
-
-    let metadata = cmd.other_options(other_options).exec()?;
-
-    let mut graph = get_dep_graph(metadata, &config)?;
-    update_dep_info(&mut graph);
-    if !config.focus.is_empty() {
-        remove_irrelevant_deps(&mut graph, &config.focus);
-    }
-    if !config.hide.is_empty() {
-        remove_deps(&mut graph, &config.hide);
-    }
-    if config.dedup_transitive_deps {
-        dedup_transitive_deps(&mut graph);
-    }
-    set_name_stats(&mut graph);
-
-    println!("{:?}", dot(&graph));
-
-    Ok(())
-}
 
-fn cli_args(opt_name: &str, val: &str) -> impl Iterator<Item = String> {
-    iter::once(opt_name.into()).chain(iter::once(val.into()))
+    let (metadata, workspaces) = load_metadata(&config)?;
+    let graph = build_graph(metadata, &workspaces, &config)?;
+    write_graph(&graph, &config, None)
 }
@@ -1,73 +1,359 @@
-use petgraph::dot::{Config, Dot};
+use std::{
+    borrow::Cow,
+    env,
+    fs::File,
+    io::{self, BufWriter, IsTerminal, Write},
+    path::Path,
+};
 
-use crate::{dep_info::DepKind, graph::DepGraph};
+use anyhow::Context;
 
-pub(crate) fn dot(graph: &DepGraph) -> Dot<'_, &DepGraph> {
-    Dot::with_attr_getters(
-        graph,
-        &[Config::EdgeNoLabel],
-        &|_, edge| {
-            let dep = edge.weight();
-            let mut attrs = Vec::new();
+use petgraph::visit::{EdgeRef, IntoEdgeReferences};
 
-            if let Some(attr) = attr_for_dep_kind(dep.kind) {
-                attrs.push(attr);
-            }
+use crate::{
+    cli::Config,
+    dep_info::{DepKind, Dependency},
+    graph::DepGraph,
+    package::Package,
+};
 
-            if dep.is_target_dep {
-                attrs.push("arrowType = empty");
-                attrs.push("fillcolor = lightgrey");
-            }
+// CycloneDX SBOM output
+mod cyclonedx;
+// Cypher statements, for loading the graph into Neo4j
+mod cypher;
+// D2 diagram output
+mod d2;
+// DOT output, for rendering with Graphviz
+mod dot;
+// Dependency structure matrix output, as CSV or HTML
+mod dsm;
+// GEXF output, for analysis in Gephi
+mod gexf;
+// GraphML output, for analysis in yEd, Cytoscape and similar tools
+mod graphml;
+// PlantUML diagram output
+mod plantuml;
+// Typed properties of packages and dependencies for the GraphML and GEXF output
+mod properties;
+// Decides which theme attributes apply to a package or dependency, and interprets them for the
+// formats other than DOT, so all formats use consistent shapes, colors and line styles
+mod style;
+// Packages and dependencies as CSV / TSV tables, with some metrics for every package
+mod table;
+// Themes mapping dependency kinds etc. to DOT attributes
+mod theme;
+// SVG output based on the built-in layout, for when Graphviz isn't available
+mod svg;
+// Scopes and package URLs for the CycloneDX and SPDX output
+mod sbom;
+// SPDX SBOM output
+mod spdx;
+// SQLite database output, for querying the graph with SQL
+#[cfg(feature = "sqlite")]
+mod sqlite;
+// Layered diagram drawn with box-drawing characters, for viewing in a terminal
+mod terminal;
+// Indented text tree, similar to `cargo tree`
+mod tree;
 
-            if dep.is_optional_direct {
-                attrs.push("style = dotted");
-            } else if dep.is_optional {
-                attrs.push("style = dashed");
-            }
+pub(crate) use self::theme::BUILTIN_THEMES;
+use self::{
+    cyclonedx::write_cyclonedx,
+    cypher::write_cypher,
+    d2::write_d2,
+    dot::write_dot,
+    dsm::{write_dsm_csv, write_dsm_html},
+    gexf::write_gexf,
+    graphml::write_graphml,
+    plantuml::write_plantuml,
+    spdx::write_spdx,
+    svg::write_svg,
+    table::{Separator, write_edges, write_nodes},
+    terminal::{TerminalOptions, write_terminal},
+    theme::Theme,
+    tree::write_tree,
+};
 
-            attrs.join(", ")
-        },
-        &|_, (_, pkg)| {
-            let mut attrs = Vec::new();
+pub(crate) fn write_output(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+) -> anyhow::Result<()> {
+    match config.output_format.as_str() {
+        "dot" => write_dot(out, graph, config, &load_theme(config)?)?,
+        "tree" => write_tree(out, graph)?,
+        "graphml" => write_graphml(out, graph)?,
+        "gexf" => write_gexf(out, graph)?,
+        "cyclonedx" => write_cyclonedx(out, graph)?,
+        "spdx" => write_spdx(out, graph)?,
+        "cypher" => write_cypher(out, graph, config.project.as_deref())?,
+        "d2" => write_d2(out, graph, config, &load_theme(config)?)?,
+        "dsm-csv" => write_dsm_csv(out, graph)?,
+        "dsm-html" => write_dsm_html(out, graph, config, &load_theme(config)?)?,
+        "plantuml" => write_plantuml(out, graph, config, &load_theme(config)?)?,
+        format @ ("unicode" | "ascii") => {
+            let color = match config.color.as_str() {
+                "always" => true,
+                "never" => false,
+                _ => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            };
+            let max_width = config.max_width.unwrap_or_else(|| {
+                env::var("COLUMNS").ok().and_then(|c| c.parse().ok()).unwrap_or(120)
+            });
+            let options = TerminalOptions { ascii: format == "ascii", color, max_width };
+            write_terminal(out, graph, &options)?;
+        }
+        format => unreachable!("unknown output format `{format}`"),
+    }
 
-            if pkg.is_ws_member {
-                attrs.push("shape = box");
-            }
+    Ok(())
+}
 
-            if let Some(attr) = attr_for_dep_kind(pkg.dep_info.kind) {
-                attrs.push(attr);
-            }
+/// Writes the graph to the given file in the configured output format.
+pub(crate) fn write_output_file(
+    path: &str,
+    graph: &DepGraph,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let file = File::create(path).with_context(|| format!("failed to create `{path}`"))?;
+    let mut out = BufWriter::new(file);
+    write_output(&mut out, graph, config)?;
+    out.flush()?;
 
-            match (pkg.dep_info.is_target_dep, pkg.dep_info.is_optional) {
-                (true, true) => {
-                    attrs.push("style = \"dashed,filled\"");
-                    attrs.push("fillcolor = lightgrey");
-                }
-                (true, false) => {
-                    attrs.push("style = filled");
-                    attrs.push("fillcolor = lightgrey");
-                }
-                (false, true) => {
-                    attrs.push("style = dashed");
-                }
-                (false, false) => {}
-            }
+    Ok(())
+}
+
+/// Lays out the graph without Graphviz and writes it to the given file as SVG.
+pub(crate) fn write_svg_file(path: &str, graph: &DepGraph, config: &Config) -> anyhow::Result<()> {
+    let theme = load_theme(config)?;
+    let file = File::create(path).with_context(|| format!("failed to create `{path}`"))?;
+    let mut out = BufWriter::new(file);
+    write_svg(&mut out, graph, config, &theme)?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Writes the packages and dependencies as tables to `nodes.csv` and `edges.csv` (or `.tsv`) in
+/// the output directory.
+pub(crate) fn write_table_files(graph: &DepGraph, config: &Config) -> anyhow::Result<()> {
+    let (separator, extension) = match config.output_format.as_str() {
+        "tsv" => (Separator::Tab, "tsv"),
+        _ => (Separator::Comma, "csv"),
+    };
+    let dir = Path::new(&config.output_dir);
+
+    let path = dir.join(format!("nodes.{extension}"));
+    let file =
+        File::create(&path).with_context(|| format!("failed to create `{}`", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_nodes(&mut out, graph, separator)?;
+    out.flush()?;
+
+    let path = dir.join(format!("edges.{extension}"));
+    let file =
+        File::create(&path).with_context(|| format!("failed to create `{}`", path.display()))?;
+    let mut out = BufWriter::new(file);
+    write_edges(&mut out, graph, separator)?;
+    out.flush()?;
+
+    Ok(())
+}
 
-            attrs.join(", ")
-        },
+/// Writes the graph to a new SQLite database at the given path, replacing any existing file.
+#[cfg(feature = "sqlite")]
+pub(crate) fn write_sqlite_file(path: &str, graph: &DepGraph) -> anyhow::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(e).with_context(|| format!("failed to remove `{path}`"));
+        }
+        _ => {}
+    }
+
+    let mut conn =
+        rusqlite::Connection::open(path).with_context(|| format!("failed to create `{path}`"))?;
+    sqlite::write_sqlite(&mut conn, graph)
+        .with_context(|| format!("failed to write the graph to `{path}`"))?;
+
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub(crate) fn write_sqlite_file(_path: &str, _graph: &DepGraph) -> anyhow::Result<()> {
+    anyhow::bail!(
+        "cargo-depgraph was built without SQLite support, \
+         reinstall it with `--features sqlite` to use --sqlite"
     )
 }
 
-fn attr_for_dep_kind(kind: DepKind) -> Option<&'static str> {
-    match kind {
-        DepKind::NORMAL => None,
-        DepKind::DEV => Some("color = blue"),
-        DepKind::BUILD => Some("color = green3"),
-        DepKind::BUILD_OF_DEV => Some("color = turquoise3"),
-        DepKind::NORMAL_AND_BUILD => Some("color = darkgreen"),
-        DepKind::DEV_AND_BUILD => Some("color = darkviolet"),
-        DepKind::NORMAL_AND_BUILD_OF_DEV => Some("color = turquoise4"),
-        DepKind::DEV_AND_BUILD_OF_DEV => Some("color = steelblue"),
-        DepKind::UNKNOWN => Some("color = red"),
+fn load_theme(config: &Config) -> anyhow::Result<Theme> {
+    match &config.theme_file {
+        Some(path) => Theme::from_file(path),
+        None => Theme::builtin(&config.theme),
+    }
+}
+
+/// All packages, sorted by stable identifiers rather than by their index in the graph, so unrelated
+/// changes to the dependency tree only cause small changes in the output.
+fn sorted_nodes(graph: &DepGraph) -> Vec<&Package> {
+    let mut nodes: Vec<_> = graph.node_weights().collect();
+    nodes.sort_by(|a, b| (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id)));
+    nodes
+}
+
+/// All dependency edges along with their source and target, sorted like `sorted_nodes`.
+fn sorted_edges(graph: &DepGraph) -> Vec<(&Package, &Package, &Dependency)> {
+    let mut edges: Vec<_> = graph
+        .edge_references()
+        .map(|edge| (&graph[edge.source()], &graph[edge.target()], edge.weight()))
+        .collect();
+    edges.sort_by_cached_key(|(source, target, dep)| {
+        (
+            (&source.name, &source.version, &source.id),
+            (&target.name, &target.version, &target.id),
+            dep.info.kind.to_string(),
+            dep.target.clone(),
+            dep.info.is_optional,
+        )
+    });
+
+    edges
+}
+
+/// Label lines for a dependency edge: its kind if it is part of a selected path, the parts of
+/// merged edges and, with --edge-labels, how it (or each of the parts) is specified in Cargo.toml.
+fn edge_label(dep: &Dependency, target: &Package, config: &Config) -> Vec<String> {
+    let mut label = Vec::new();
+    if dep.info.is_on_path {
+        label.push(dep.info.kind.to_string());
+    }
+    for part in &dep.merged_from {
+        let mut line = match &part.target {
+            Some(platform) => format!("{} ({platform})", part.info.kind),
+            None => part.info.kind.to_string(),
+        };
+        let manifest = if config.edge_labels { manifest_label(part, target) } else { Vec::new() };
+        if !manifest.is_empty() {
+            line += &format!(": {}", manifest.join(", "));
+        }
+        label.push(line);
+    }
+    if config.edge_labels && dep.merged_from.is_empty() {
+        label.extend(manifest_label(dep, target));
+    }
+
+    label
+}
+
+/// Label lines describing how a dependency is specified in the dependent's Cargo.toml.
+fn manifest_label(dep: &Dependency, target: &Package) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(rename) = &dep.rename {
+        lines.push(format!("{rename} = {}", target.name));
+    }
+    if let Some(req) = &dep.req {
+        lines.push(req.to_string());
+    }
+    if !dep.uses_default_features {
+        lines.push("default-features = false".to_owned());
+    }
+    if !dep.features.is_empty() {
+        lines.push(format!("features = [{}]", dep.features.join(", ")));
+    }
+
+    lines
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Percent-encodes everything except unreserved characters, `:` and `/`, for use in a URL.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'/' => {
+                encoded.push(byte.into());
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+
+    encoded
+}
+
+/// Quotes a CSV field if necessary.
+fn escape_csv(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+fn describe(kind: DepKind, is_target_dep: bool, optional: Option<&str>) -> String {
+    let mut description = kind.to_string();
+    if let Some(optional) = optional {
+        description += ", ";
+        description += optional;
+    }
+    if is_target_dep {
+        description += ", target-specific";
+    }
+
+    description
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::parse_view_options,
+        dep_info::DepInfo,
+        graph::{merge_parallel_edges, test_graph},
+    };
+
+    #[test]
+    fn merged_edge_labels_describe_each_part() {
+        let mut graph = test_graph(
+            &["app"],
+            &[("app", "util", DepKind::NORMAL), ("app", "util", DepKind::BUILD)],
+        );
+        for edge in graph.edge_weights_mut() {
+            if edge.info.kind == DepKind::NORMAL {
+                edge.req = Some("^0.1".parse().unwrap());
+                edge.features = vec!["a".to_owned()];
+            } else {
+                edge.req = Some("*".parse().unwrap());
+                edge.target = Some("cfg(unix)".to_owned());
+            }
+        }
+        merge_parallel_edges(&mut graph);
+
+        let config = parse_view_options(&["--edge-labels".to_owned()]).unwrap();
+        let (_, target, dep) = sorted_edges(&graph)[0];
+        assert_eq!(
+            edge_label(dep, target, &config),
+            ["build (cfg(unix)): *", "normal: ^0.1, features = [a]"]
+        );
+
+        let config = parse_view_options(&[]).unwrap();
+        assert_eq!(edge_label(dep, target, &config), ["build (cfg(unix))", "normal"]);
+    }
+
+    #[test]
+    fn edge_labels_describe_the_manifest_entry() {
+        let mut dep = Dependency::new(DepInfo::default());
+        dep.rename = Some("utils".to_owned());
+        dep.req = Some("^0.1".parse().unwrap());
+        dep.uses_default_features = false;
+        let target = Package::test("util", false);
+
+        let config = parse_view_options(&["--edge-labels".to_owned()]).unwrap();
+        assert_eq!(
+            edge_label(&dep, &target, &config),
+            ["utils = util", "^0.1", "default-features = false"]
+        );
     }
 }
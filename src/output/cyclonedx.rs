@@ -0,0 +1,99 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+};
+
+use serde_json::{Value, json};
+
+use crate::{graph::DepGraph, package::Package};
+
+use super::{
+    properties::{NODE_PROPERTIES, node_properties},
+    sbom::{is_included, purl, scope, timestamp},
+    sorted_edges, sorted_nodes,
+};
+
+/// Writes the graph as a CycloneDX SBOM in JSON format, with the scope of every package derived
+/// from how it is used by the workspace members and the dependencies between them.
+///
+/// If there is only one workspace member, it is described as the subject of the SBOM rather than
+/// one of its components.
+pub(crate) fn write_cyclonedx(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    let packages: Vec<_> = sorted_nodes(graph).into_iter().filter(|pkg| is_included(pkg)).collect();
+    let ws_members: Vec<_> = packages.iter().filter(|pkg| pkg.is_ws_member).collect();
+    let subject = match ws_members[..] {
+        [pkg] => Some(pkg),
+        _ => None,
+    };
+
+    let mut metadata = json!({
+        "timestamp": timestamp(),
+        "tools": {
+            "components": [{
+                "type": "application",
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            }],
+        },
+    });
+    if let Some(pkg) = subject {
+        metadata["component"] = component(pkg, "application");
+    }
+
+    let components: Vec<_> = packages
+        .iter()
+        .filter(|pkg| subject.is_none_or(|subject| subject.id != pkg.id))
+        .map(|pkg| component(pkg, "library"))
+        .collect();
+
+    let mut dependencies: BTreeMap<_, _> =
+        packages.iter().map(|pkg| (&pkg.id, BTreeSet::new())).collect();
+    for (source, target, _) in sorted_edges(graph) {
+        if is_included(source) && is_included(target) {
+            dependencies.get_mut(&source.id).unwrap().insert(&target.id);
+        }
+    }
+    let dependencies: Vec<_> = dependencies
+        .into_iter()
+        .map(|(id, deps)| json!({ "ref": id, "dependsOn": deps }))
+        .collect();
+
+    let bom = json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": metadata,
+        "components": components,
+        "dependencies": dependencies,
+    });
+    serde_json::to_writer_pretty(&mut *out, &bom)?;
+    writeln!(out)
+}
+
+fn component(pkg: &Package, ty: &str) -> Value {
+    let mut component = json!({
+        "type": ty,
+        "bom-ref": pkg.id,
+        "name": pkg.name,
+        "version": pkg.version.to_string(),
+    });
+    if let Some(scope) = scope(&pkg.dep_info) {
+        component["scope"] = scope.as_str().into();
+    }
+    if let Some(purl) = purl(pkg) {
+        component["purl"] = purl.into();
+    }
+
+    // Everything else cargo-depgraph knows about the package, as custom properties
+    let properties: Vec<_> = NODE_PROPERTIES
+        .iter()
+        .zip(node_properties(pkg))
+        .filter(|((name, _), _)| !["name", "version"].contains(name))
+        .filter_map(|((name, _), value)| {
+            Some(json!({ "name": format!("cargo-depgraph:{name}"), "value": value? }))
+        })
+        .collect();
+    component["properties"] = properties.into();
+
+    component
+}
@@ -0,0 +1,153 @@
+use std::io::{self, Write};
+
+use crate::{
+    graph::DepGraph,
+    package::{Package, PackageSource},
+};
+
+use super::{
+    properties::{
+        EDGE_PROPERTIES, NODE_PROPERTIES, PropertyType, edge_properties, node_properties,
+    },
+    sorted_edges, sorted_nodes,
+};
+
+/// The properties that identify a package node, so writing the same graph twice (or graphs of
+/// several projects sharing dependencies) doesn't create duplicates.
+///
+/// Placeholders for pruned dependencies don't have a version and source, they are identified by
+/// their `id` (and project) instead.
+const PACKAGE_KEY: [&str; 3] = ["name", "version", "source"];
+
+/// The properties that identify a dependency relationship, in addition to its endpoints and the
+/// project. There can be multiple dependencies between two packages that differ in these.
+const DEPENDENCY_KEY: [&str; 2] = ["kind", "platform"];
+
+/// Writes the graph as Cypher statements (e.g. for Neo4j) that create or update `Package` nodes
+/// and `DEPENDS_ON` relationships with the properties of packages and dependencies.
+///
+/// If a project is given, relationships are keyed by it too, and packages get it added to their
+/// list of `projects`, so multiple projects can be loaded into the same database.
+pub(crate) fn write_cypher(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    project: Option<&str>,
+) -> io::Result<()> {
+    writeln!(
+        out,
+        "CREATE INDEX package_key IF NOT EXISTS FOR (p:Package) ON (p.{});",
+        PACKAGE_KEY.join(", p."),
+    )?;
+
+    for pkg in sorted_nodes(graph) {
+        let key: &[&str] =
+            if matches!(pkg.source, PackageSource::Placeholder(_)) { &[] } else { &PACKAGE_KEY };
+        let values = NODE_PROPERTIES.iter().zip(node_properties(pkg));
+        let mut assignments: Vec<_> = values
+            .filter(|((name, _), _)| !key.contains(name))
+            .filter_map(|((name, ty), value)| Some(format!("p.{name} = {}", literal(*ty, &value?))))
+            .collect();
+        if let Some(project) = project {
+            let project = string(project);
+            assignments.push(format!(
+                "p.projects = coalesce(p.projects, []) + \
+                 CASE WHEN {project} IN coalesce(p.projects, []) THEN [] ELSE [{project}] END"
+            ));
+        }
+
+        let key = package_key(pkg, project);
+        writeln!(out, "MERGE (p:Package {key}) SET {};", assignments.join(", "))?;
+    }
+
+    for (source, target, dep) in sorted_edges(graph) {
+        let mut key = Vec::new();
+        let mut assignments = Vec::new();
+        for ((name, ty), value) in EDGE_PROPERTIES.iter().zip(edge_properties(dep)) {
+            if DEPENDENCY_KEY.contains(name) {
+                // MERGE doesn't support null properties
+                key.push(format!("{name}: {}", string(value.as_deref().unwrap_or_default())));
+            } else if let Some(value) = value {
+                assignments.push(format!("d.{name} = {}", literal(*ty, &value)));
+            }
+        }
+        if let Some(project) = project {
+            key.push(format!("project: {}", string(project)));
+        }
+
+        writeln!(
+            out,
+            "MATCH (a:Package {}), (b:Package {}) MERGE (a)-[d:DEPENDS_ON {{{}}}]->(b) SET {};",
+            package_key(source, project),
+            package_key(target, project),
+            key.join(", "),
+            assignments.join(", "),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn package_key(pkg: &Package, project: Option<&str>) -> String {
+    if matches!(pkg.source, PackageSource::Placeholder(_)) {
+        let project = project.map(|project| format!(", project: {}", string(project)));
+        return format!("{{id: {}{}}}", string(&pkg.id), project.unwrap_or_default());
+    }
+
+    let values = NODE_PROPERTIES.iter().zip(node_properties(pkg));
+    let key: Vec<_> = values
+        .filter(|((name, _), _)| PACKAGE_KEY.contains(name))
+        .map(|((name, _), value)| {
+            format!("{name}: {}", string(value.as_deref().unwrap_or_default()))
+        })
+        .collect();
+
+    format!("{{{}}}", key.join(", "))
+}
+
+fn literal(ty: PropertyType, value: &str) -> String {
+    match ty {
+        PropertyType::String => string(value),
+        PropertyType::Boolean => value.to_owned(),
+    }
+}
+
+fn string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dep_info::DepKind,
+        graph::{prune_deps, test_graph},
+        util::set_name_stats,
+    };
+
+    #[test]
+    fn placeholders_are_keyed_by_id() {
+        let mut graph = test_graph(
+            &["app"],
+            &[
+                ("app", "a", DepKind::NORMAL),
+                ("app", "b", DepKind::NORMAL),
+                ("a", "a-dep", DepKind::NORMAL),
+                ("b", "b-dep", DepKind::NORMAL),
+            ],
+        );
+        prune_deps(&mut graph, &["a".to_owned(), "b".to_owned()]);
+        set_name_stats(&mut graph);
+
+        let mut out = Vec::new();
+        write_cypher(&mut out, &graph, Some("demo")).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let placeholders: Vec<_> =
+            out.lines().filter(|line| line.starts_with("MERGE (p:Package {id:")).collect();
+        assert_eq!(placeholders.len(), 2);
+        assert!(placeholders[0].starts_with(
+            "MERGE (p:Package {id: 'a@0.1.0 (pruned)', project: 'demo'}) SET p.name = '+1 crates'"
+        ));
+        assert!(placeholders[1].starts_with("MERGE (p:Package {id: 'b@0.1.0 (pruned)'"));
+    }
+}
@@ -0,0 +1,177 @@
+use std::io::{self, Write};
+
+use crate::{cli::Config, graph::DepGraph};
+
+use super::{
+    edge_label, sorted_edges, sorted_nodes,
+    style::{ArrowHead, LineStyle, Shape, background_color, edge_style, node_style},
+    theme::Theme,
+};
+
+/// Writes the graph in the D2 diagram language, with the same shapes, colors and line styles as
+/// the DOT output as far as D2 supports them.
+pub(crate) fn write_d2(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    let direction = match config.rankdir.as_deref() {
+        Some("LR") => "right",
+        Some("BT") => "up",
+        Some("RL") => "left",
+        _ => "down",
+    };
+    writeln!(out, "direction: {direction}")?;
+    if let Some(bgcolor) = background_color(theme) {
+        writeln!(out, "style.fill: {}", quote(&bgcolor))?;
+    }
+
+    for pkg in sorted_nodes(graph) {
+        let style = node_style(pkg, config, theme);
+        // D2 has no note, octagon or folder shapes, these are the closest ones
+        let shape = match style.shape {
+            Shape::Ellipse => "oval",
+            Shape::Box => "rectangle",
+            Shape::Note => "page",
+            Shape::Hexagon => "hexagon",
+            // A hexagon would be closer, but that's already used for alternative registries in
+            // the built-in themes, and git dependencies should still look different
+            Shape::Octagon => "parallelogram",
+            Shape::Folder => "package",
+            Shape::Point => "circle",
+        };
+
+        writeln!(out, "{}: {} {{", quote(&pkg.id), quote(&format!("{pkg:?}")))?;
+        writeln!(out, "  shape: {shape}")?;
+        writeln!(out, "  style.stroke: {}", quote(&style.color))?;
+        if let Some(fill_color) = &style.fill_color {
+            writeln!(out, "  style.fill: {}", quote(fill_color))?;
+        }
+        writeln!(out, "  style.font-color: {}", quote(&style.font_color))?;
+        write_line_style(out, style.line, style.pen_width)?;
+        if style.double_border {
+            // D2 only supports double borders for rectangles and ovals
+            match style.shape {
+                Shape::Box | Shape::Ellipse => writeln!(out, "  style.double-border: true")?,
+                _ => writeln!(out, "  style.multiple: true")?,
+            }
+        }
+        writeln!(out, "}}")?;
+    }
+
+    for (source, target, dep) in sorted_edges(graph) {
+        let style = edge_style(&dep.info, config, theme);
+        let arrow = if style.arrow_head == ArrowHead::None { "--" } else { "->" };
+
+        write!(out, "{} {arrow} {}", quote(&source.id), quote(&target.id))?;
+        let label = edge_label(dep, target, config);
+        if !label.is_empty() {
+            write!(out, ": {}", quote(&label.join("\n")))?;
+        }
+        writeln!(out, " {{")?;
+        writeln!(out, "  style.stroke: {}", quote(&style.color))?;
+        writeln!(out, "  style.font-color: {}", quote(&style.font_color))?;
+        write_line_style(out, style.line, style.pen_width)?;
+        if style.arrow_head == ArrowHead::Empty {
+            writeln!(out, "  target-arrowhead.style.filled: false")?;
+        }
+        writeln!(out, "}}")?;
+    }
+
+    Ok(())
+}
+
+fn write_line_style(out: &mut impl Write, line: LineStyle, pen_width: f64) -> io::Result<()> {
+    match line {
+        LineStyle::Solid => {}
+        LineStyle::Dashed => writeln!(out, "  style.stroke-dash: 5")?,
+        LineStyle::Dotted => writeln!(out, "  style.stroke-dash: 2")?,
+        LineStyle::Invisible => writeln!(out, "  style.opacity: 0")?,
+    }
+
+    // D2 only supports integer stroke widths from 1 to 15
+    let stroke_width = pen_width.round().clamp(1.0, 15.0);
+    if stroke_width != 1.0 {
+        writeln!(out, "  style.stroke-width: {stroke_width}")?;
+    }
+
+    Ok(())
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::parse_view_options, dep_info::DepKind, graph::test_graph, package::PackageSource,
+    };
+
+    #[test]
+    fn styles_follow_the_theme() {
+        let mut graph = test_graph(
+            &["app"],
+            &[("app", "git-dep", DepKind::DEV), ("app", "registry-dep", DepKind::NORMAL)],
+        );
+        for pkg in graph.node_weights_mut() {
+            match pkg.name.as_str() {
+                "git-dep" => pkg.source = PackageSource::Git("https://example.com/git-dep".into()),
+                "registry-dep" => {
+                    pkg.source = PackageSource::Registry("https://example.com/index".into());
+                    pkg.is_proc_macro = true;
+                    pkg.dep_info.is_target_dep = true;
+                }
+                _ => {}
+            }
+        }
+        for dep in graph.edge_weights_mut() {
+            dep.info.is_target_dep = dep.info.kind == DepKind::NORMAL;
+            dep.info.is_optional = dep.info.kind == DepKind::DEV;
+        }
+        let config = parse_view_options(&["--dev-deps".to_owned()]).unwrap();
+        let mut out = Vec::new();
+        write_d2(&mut out, &graph, &config, &Theme::builtin("default").unwrap()).unwrap();
+        let d2 = String::from_utf8(out).unwrap();
+
+        let block = |header: &str| {
+            let start = d2.find(&format!("{header} {{\n")).unwrap_or_else(|| panic!("{d2}"));
+            let block = &d2[start..];
+            block[..block.find("}\n").unwrap()].lines().skip(1).collect::<Vec<_>>()
+        };
+        assert_eq!(
+            block(r#""app@0.1.0": "app""#),
+            ["  shape: rectangle", r#"  style.stroke: "black""#, r#"  style.font-color: "black""#]
+        );
+        assert_eq!(block(r#""git-dep@0.1.0": "git-dep""#)[0], "  shape: parallelogram");
+        assert_eq!(
+            block(r#""registry-dep@0.1.0": "registry-dep""#),
+            [
+                "  shape: hexagon",
+                r#"  style.stroke: "black""#,
+                r#"  style.fill: "lightgrey""#,
+                r#"  style.font-color: "black""#,
+                // The double border of proc-macros is only supported for some shapes
+                "  style.multiple: true",
+            ]
+        );
+        assert_eq!(
+            block(r#""app@0.1.0" -> "git-dep@0.1.0""#),
+            [
+                r#"  style.stroke: "blue""#,
+                r#"  style.font-color: "black""#,
+                "  style.stroke-dash: 5"
+            ]
+        );
+        assert_eq!(
+            block(r#""app@0.1.0" -> "registry-dep@0.1.0""#),
+            [
+                r#"  style.stroke: "black""#,
+                r#"  style.font-color: "black""#,
+                "  target-arrowhead.style.filled: false",
+            ]
+        );
+    }
+}
@@ -0,0 +1,392 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use crate::{
+    cli::Config,
+    dep_info::{DepInfo, Dependency},
+    graph::{DepGraph, node_depths},
+    package::{Package, PackageSource},
+};
+
+use super::{
+    describe, edge_label, percent_encode, sorted_edges, sorted_nodes,
+    style::{edge_attrs, node_attrs, node_shape, package_attrs, workspace_color},
+    theme::{Attrs, Theme, quote},
+};
+
+pub(crate) fn write_dot(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    writeln!(out, "digraph {{")?;
+
+    let mut graph_attrs = theme.graph.clone();
+    if let Some(rankdir) = &config.rankdir {
+        graph_attrs.set("rankdir", rankdir.as_str());
+    }
+
+    for (name, attrs) in [("graph", &graph_attrs), ("node", &theme.node), ("edge", &theme.edge)] {
+        if !attrs.is_empty() {
+            writeln!(out, "    {name} [{attrs}]")?;
+        }
+    }
+
+    let nodes = sorted_nodes(graph);
+    let edges = sorted_edges(graph);
+
+    for &pkg in &nodes {
+        let mut attrs = Attrs::new();
+        attrs.set("label", format!("{pkg:?}"));
+        attrs.extend(&package_attrs(pkg, config, theme));
+        if config.svg_attrs {
+            attrs.extend(&node_svg_attrs(pkg, config));
+        }
+
+        writeln!(out, "    {} [{attrs}]", quote(&pkg.id))?;
+    }
+
+    for &(source, target, dep) in &edges {
+        let mut attrs = edge_attrs(&dep.info, config, theme);
+
+        let label = edge_label(dep, target, config);
+        if !label.is_empty() {
+            attrs.set("label", label.join("\n"));
+        }
+        if config.svg_attrs {
+            attrs.extend(&edge_svg_attrs(source, target, dep));
+        }
+
+        writeln!(out, "    {} -> {} [{attrs}]", quote(&source.id), quote(&target.id))?;
+    }
+
+    if config.cluster_workspaces {
+        write_workspace_clusters(out, &nodes, config)?;
+    }
+
+    if let Some(rank_by) = &config.rank_by {
+        let depths = node_depths(graph, rank_by == "reverse-depth");
+        let mut ranks: BTreeMap<u32, Vec<&str>> = BTreeMap::new();
+        for (idx, depth) in depths {
+            ranks.entry(depth).or_default().push(&graph[idx].id);
+        }
+
+        for mut ids in ranks.into_values() {
+            ids.sort_unstable();
+            let ids: Vec<_> = ids.into_iter().map(quote).collect();
+            writeln!(out, "    {{ rank = same; {}; }}", ids.join("; "))?;
+        }
+    }
+
+    if config.legend {
+        let edges: Vec<_> = edges.iter().map(|&(_, _, dep)| &dep.info).collect();
+        write_legend(out, &nodes, &edges, config, theme)?;
+    }
+
+    writeln!(out, "}}")
+}
+
+/// Writes a cluster for each workspace, containing its members.
+fn write_workspace_clusters(
+    out: &mut impl Write,
+    nodes: &[&Package],
+    config: &Config,
+) -> io::Result<()> {
+    let mut workspaces: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for pkg in nodes {
+        if let Some(workspace) = pkg.workspace {
+            workspaces.entry(workspace).or_default().push(&pkg.id);
+        }
+    }
+
+    for (workspace, ids) in workspaces {
+        let mut attrs = Attrs::new();
+        attrs.set("label", config.manifest_path[workspace].as_str());
+        attrs.set("color", workspace_color(workspace));
+        attrs.set("fontcolor", workspace_color(workspace));
+
+        writeln!(out, "    subgraph \"cluster_workspace_{workspace}\" {{")?;
+        writeln!(out, "        graph [{attrs}]")?;
+        for id in ids {
+            writeln!(out, "        {}", quote(id))?;
+        }
+        writeln!(out, "    }}")?;
+    }
+
+    Ok(())
+}
+
+/// Writes a cluster containing a sample for every node shape, node style and edge style that is
+/// used in the graph.
+fn write_legend(
+    out: &mut impl Write,
+    nodes: &[&Package],
+    edges: &[&DepInfo],
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    let mut shapes = Vec::new();
+    let mut node_styles = Vec::new();
+    for pkg in nodes {
+        if let Some((shape, description)) = node_shape(pkg, theme)
+            && !shapes.iter().any(|&(_, d)| d == description)
+        {
+            shapes.push((shape, description));
+        }
+        if pkg.is_proc_macro
+            && !theme.node_state.proc_macro.is_empty()
+            && !shapes.iter().any(|&(_, d)| d == "proc-macro")
+        {
+            shapes.push((&theme.node_state.proc_macro, "proc-macro"));
+        }
+
+        let info = &pkg.dep_info;
+        let style = (info.kind, info.is_target_dep, info.is_optional);
+        if !node_styles.contains(&style) {
+            node_styles.push(style);
+        }
+    }
+
+    let mut edge_styles = Vec::new();
+    for dep in edges {
+        let style = (dep.kind, dep.is_target_dep, dep.is_optional, dep.is_optional_direct);
+        if !edge_styles.contains(&style) {
+            edge_styles.push(style);
+        }
+    }
+
+    writeln!(out, "    subgraph cluster_legend {{")?;
+    writeln!(out, "        label = \"Legend\"")?;
+
+    for (i, (shape, description)) in shapes.into_iter().enumerate() {
+        let mut attrs = Attrs::new();
+        attrs.set("label", description);
+        attrs.extend(shape);
+        writeln!(out, "        legend_shape_{i} [{attrs}]")?;
+    }
+
+    for (i, (kind, is_target_dep, is_optional)) in node_styles.into_iter().enumerate() {
+        let info = DepInfo { kind, is_target_dep, is_optional, ..DepInfo::default() };
+        let description = describe(kind, is_target_dep, is_optional.then_some("optional"));
+
+        let mut attrs = Attrs::new();
+        attrs.set("label", description);
+        attrs.extend(&node_attrs(&info, config, theme));
+        writeln!(out, "        legend_node_{i} [{attrs}]")?;
+    }
+
+    for (i, (kind, is_target_dep, is_optional, is_optional_direct)) in
+        edge_styles.into_iter().enumerate()
+    {
+        let info =
+            DepInfo { kind, is_target_dep, is_optional, is_optional_direct, ..DepInfo::default() };
+        let optional = if is_optional_direct {
+            Some("optional")
+        } else if is_optional {
+            Some("transitively optional")
+        } else {
+            None
+        };
+        let description = describe(kind, is_target_dep, optional);
+
+        let mut attrs = Attrs::new();
+        attrs.set("label", description);
+        attrs.extend(&edge_attrs(&info, config, theme));
+        writeln!(out, "        legend_edge_{i}_from [label = \"\", shape = \"point\"]")?;
+        writeln!(out, "        legend_edge_{i}_to [label = \"\", shape = \"point\"]")?;
+        writeln!(out, "        legend_edge_{i}_from -> legend_edge_{i}_to [{attrs}]")?;
+    }
+
+    writeln!(out, "    }}")
+}
+
+/// Link, tooltip and CSS classes for a package's node, for use in SVG output.
+pub(super) fn node_svg_attrs(pkg: &Package, config: &Config) -> Attrs {
+    let mut attrs = Attrs::new();
+    let info = &pkg.dep_info;
+
+    let url = match &pkg.source {
+        PackageSource::CratesIo if config.link_to == "crates.io" => {
+            Some(format!("https://crates.io/crates/{}/{}", pkg.name, pkg.version))
+        }
+        PackageSource::CratesIo => Some(format!("https://docs.rs/{}/{}", pkg.name, pkg.version)),
+        // Strip the revision from the repository URL
+        PackageSource::Git(url) => url.split(['?', '#']).next().map(ToOwned::to_owned),
+        PackageSource::Registry(_) | PackageSource::Placeholder(_) => None,
+        PackageSource::Path => pkg.path.as_deref().map(file_url),
+    };
+    if let Some(url) = url {
+        attrs.set("URL", url);
+    }
+
+    let mut tooltip = match pkg.source {
+        PackageSource::Placeholder(_) => format!("{}\n", pkg.name),
+        _ => format!("{} {}\n", pkg.name, pkg.version),
+    };
+    tooltip += &describe(info.kind, info.is_target_dep, info.is_optional.then_some("optional"));
+    match &pkg.source {
+        PackageSource::CratesIo => {}
+        PackageSource::Registry(url) => tooltip += &format!("\nfrom registry {url}"),
+        PackageSource::Git(url) => tooltip += &format!("\nfrom git repository {url}"),
+        PackageSource::Path => tooltip += "\nfrom local path",
+        PackageSource::Placeholder(_) => tooltip += "\npruned dependencies",
+    }
+    attrs.set("tooltip", tooltip);
+
+    let mut classes = vec![format!("kind-{}", info.kind.as_str())];
+    let source_class = match pkg.source {
+        PackageSource::CratesIo => "source-crates-io",
+        PackageSource::Registry(_) => "source-alt-registry",
+        PackageSource::Git(_) => "source-git",
+        PackageSource::Path => "source-path",
+        PackageSource::Placeholder(_) => "pruned",
+    };
+    classes.push(source_class.to_owned());
+    for (flag, class) in [
+        (pkg.is_ws_member, "ws-member"),
+        (pkg.is_proc_macro, "proc-macro"),
+        (info.is_optional, "optional"),
+        (info.is_target_dep, "target-dep"),
+        (info.is_on_path, "on-path"),
+    ] {
+        if flag {
+            classes.push(class.to_owned());
+        }
+    }
+    attrs.set("class", classes.join(" "));
+
+    attrs
+}
+
+/// A `file://` URL for a local directory, which also works for Windows paths like `C:\foo`.
+fn file_url(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    format!("file://{separator}{}", percent_encode(&path))
+}
+
+/// Tooltip and CSS classes for a dependency edge, for use in SVG output.
+pub(super) fn edge_svg_attrs(source: &Package, target: &Package, dep: &Dependency) -> Attrs {
+    let mut attrs = Attrs::new();
+    let info = &dep.info;
+
+    let optional = if info.is_optional_direct {
+        Some("optional")
+    } else if info.is_optional {
+        Some("transitively optional")
+    } else {
+        None
+    };
+    let mut tooltip = format!("{} -> {}\n", source.name, target.name);
+    tooltip += &describe(info.kind, info.is_target_dep, optional);
+    if let Some(target) = &dep.target {
+        tooltip += &format!(" ({target})");
+    }
+    attrs.set("tooltip", tooltip);
+
+    let mut classes = vec![format!("kind-{}", info.kind.as_str())];
+    for (flag, class) in [
+        (info.is_optional_direct, "optional"),
+        (info.is_optional && !info.is_optional_direct, "transitively-optional"),
+        (info.is_target_dep, "target-dep"),
+        (info.is_on_path, "on-path"),
+    ] {
+        if flag {
+            classes.push(class.to_owned());
+        }
+    }
+    attrs.set("class", classes.join(" "));
+
+    attrs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::parse_view_options,
+        dep_info::{DepInfo, DepKind},
+        graph::test_graph,
+    };
+
+    #[test]
+    fn placeholders_are_not_described_as_packages() {
+        let config = parse_view_options(&["--svg-attrs".to_owned()]).unwrap();
+        let placeholder = Package::placeholder(3, DepInfo::default(), "foo@1.0.0");
+        let attrs = node_svg_attrs(&placeholder, &config);
+
+        assert_eq!(attrs.get("URL"), None);
+        assert_eq!(attrs.get("tooltip"), Some("+3 crates\nnormal\npruned dependencies"));
+        assert_eq!(attrs.get("class"), Some("kind-normal pruned"));
+    }
+
+    #[test]
+    fn ids_and_attributes_are_escaped() {
+        let mut graph = test_graph(&["app"], &[("app", "util", DepKind::NORMAL)]);
+        for pkg in graph.node_weights_mut() {
+            if pkg.name == "util" {
+                pkg.id = r#"util@0.1.0 (path+C:\ws\"util"\)"#.to_owned();
+            }
+        }
+        let config = parse_view_options(&["--svg-attrs".to_owned()]).unwrap();
+        let mut out = Vec::new();
+        write_dot(&mut out, &graph, &config, &Theme::builtin("default").unwrap()).unwrap();
+        let dot = String::from_utf8(out).unwrap();
+
+        let escaped_id = r#""util@0.1.0 (path+C:\\ws\\\"util\"\\)""#;
+        assert!(dot.contains(&format!("    \"app@0.1.0\" -> {escaped_id} [")), "{dot}");
+        assert!(dot.contains(&format!("    {escaped_id} [label = \"util\"")), "{dot}");
+        // Line breaks in tooltips are written as escape sequences
+        assert!(dot.contains(r#"tooltip = "util 0.1.0\nnormal""#), "{dot}");
+    }
+
+    #[test]
+    fn path_packages_link_to_file_urls() {
+        assert_eq!(file_url("/home/me/my crate#1"), "file:///home/me/my%20crate%231");
+        assert_eq!(file_url(r"C:\ws\app"), "file:///C:/ws/app");
+
+        let config = parse_view_options(&["--svg-attrs".to_owned()]).unwrap();
+        let mut pkg = Package::test("app", true);
+        pkg.source = PackageSource::Path;
+        pkg.path = Some("/ws/app".to_owned());
+        assert_eq!(node_svg_attrs(&pkg, &config).get("URL"), Some("file:///ws/app"));
+    }
+
+    #[test]
+    fn packages_of_the_same_depth_are_ranked_together() {
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "b", DepKind::NORMAL),
+                ("app", "a", DepKind::NORMAL),
+                ("a", "c", DepKind::NORMAL),
+                ("b", "c", DepKind::NORMAL),
+            ],
+        );
+        let ranks = |args: &[&str]| {
+            let args: Vec<_> = args.iter().map(|&arg| arg.to_owned()).collect();
+            let config = parse_view_options(&args).unwrap();
+            let mut out = Vec::new();
+            write_dot(&mut out, &graph, &config, &Theme::builtin("default").unwrap()).unwrap();
+            let dot = String::from_utf8(out).unwrap();
+            dot.lines()
+                .filter(|line| line.contains("rank = same"))
+                .map(str::to_owned)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranks(&["--rank-by", "depth"]),
+            [
+                r#"    { rank = same; "app@0.1.0"; }"#,
+                r#"    { rank = same; "a@0.1.0"; "b@0.1.0"; }"#,
+                r#"    { rank = same; "c@0.1.0"; }"#,
+            ]
+        );
+        assert_eq!(ranks(&["--rank-by", "reverse-depth"]).len(), 3);
+        assert!(ranks(&[]).is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::{self, Write},
+};
+
+use petgraph::{
+    Direction,
+    algo::tarjan_scc,
+    stable_graph::NodeIndex,
+    visit::{EdgeRef, IntoEdgeReferences},
+};
+
+use crate::{
+    cli::Config,
+    dep_info::{DepInfo, DepKind},
+    graph::DepGraph,
+};
+
+use super::{escape_csv, escape_xml, style::edge_style, theme::Theme};
+
+/// A dependency structure matrix: row `i` depends on column `j` if there is a cell `(i, j)`.
+///
+/// Packages are ordered such that dependencies come before their dependents, so all cells are
+/// below the diagonal except for those caused by dependency cycles. The packages of a cycle are
+/// kept next to each other (partitioned).
+struct Dsm {
+    packages: Vec<NodeIndex<u16>>,
+    /// for every package, the index of its cycle (strongly connected component) if it is part of
+    /// one
+    cycles: Vec<Option<usize>>,
+    /// the combined info of all dependency edges between two packages
+    cells: HashMap<(usize, usize), DepInfo>,
+}
+
+impl Dsm {
+    fn new(graph: &DepGraph) -> Self {
+        let sort_key = |idx: NodeIndex<u16>| {
+            let pkg = &graph[idx];
+            (pkg.name.clone(), pkg.version.clone(), pkg.id.clone())
+        };
+
+        let mut components = tarjan_scc(graph);
+        for component in &mut components {
+            component.sort_by_cached_key(|&idx| sort_key(idx));
+        }
+        let component_of: HashMap<_, _> = components
+            .iter()
+            .enumerate()
+            .flat_map(|(i, component)| component.iter().map(move |&idx| (idx, i)))
+            .collect();
+
+        // Topologically sort the components, starting with those that don't depend on any other
+        // component and preferring the alphabetically first one if there are multiple candidates
+        let mut num_deps = vec![0; components.len()];
+        let mut dependents = vec![Vec::new(); components.len()];
+        for (i, component) in components.iter().enumerate() {
+            let mut deps: Vec<_> = component
+                .iter()
+                .flat_map(|&idx| graph.neighbors_directed(idx, Direction::Outgoing))
+                .map(|dep| component_of[&dep])
+                .filter(|&c| c != i)
+                .collect();
+            deps.sort_unstable();
+            deps.dedup();
+
+            num_deps[i] = deps.len();
+            for dep in deps {
+                dependents[dep].push(i);
+            }
+        }
+
+        let mut ready: BTreeSet<_> = (0..components.len())
+            .filter(|&i| num_deps[i] == 0)
+            .map(|i| (sort_key(components[i][0]), i))
+            .collect();
+        let mut packages = Vec::new();
+        let mut cycles = Vec::new();
+        let mut num_cycles = 0;
+        while let Some((_, i)) = ready.pop_first() {
+            let cycle = (components[i].len() > 1).then(|| {
+                num_cycles += 1;
+                num_cycles - 1
+            });
+            packages.extend(&components[i]);
+            cycles.extend(components[i].iter().map(|_| cycle));
+
+            for &dependent in &dependents[i] {
+                num_deps[dependent] -= 1;
+                if num_deps[dependent] == 0 {
+                    ready.insert((sort_key(components[dependent][0]), dependent));
+                }
+            }
+        }
+
+        let positions: HashMap<_, _> =
+            packages.iter().enumerate().map(|(pos, &idx)| (idx, pos)).collect();
+        let mut cells = HashMap::new();
+        for edge in graph.edge_references() {
+            let cell = (positions[&edge.source()], positions[&edge.target()]);
+            cells
+                .entry(cell)
+                .and_modify(|info: &mut DepInfo| info.combine_incoming(edge.weight().info))
+                .or_insert(edge.weight().info);
+        }
+
+        Self { packages, cycles, cells }
+    }
+}
+
+/// Writes a dependency structure matrix as CSV, with the packages as row and column headers and
+/// the dependency kind in each cell where the row's package depends on the column's package.
+pub(crate) fn write_dsm_csv(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    let dsm = Dsm::new(graph);
+    let labels: Vec<_> = dsm.packages.iter().map(|&idx| format!("{:?}", graph[idx])).collect();
+
+    for label in &labels {
+        write!(out, ",{}", escape_csv(label))?;
+    }
+    writeln!(out)?;
+
+    for (row, label) in labels.iter().enumerate() {
+        write!(out, "{}", escape_csv(label))?;
+        for column in 0..labels.len() {
+            match dsm.cells.get(&(row, column)) {
+                Some(info) => write!(out, ",{}", info.kind.as_str())?,
+                None => write!(out, ",")?,
+            }
+        }
+        writeln!(out)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a dependency structure matrix as a standalone HTML page, with cells colored like the
+/// edges of the DOT output. Cells above the diagonal are caused by dependency cycles and are
+/// highlighted, as are the packages that form a cycle.
+pub(crate) fn write_dsm_html(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    let dsm = Dsm::new(graph);
+
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, "<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Dependency structure matrix</title>")?;
+    writeln!(out, "<style>{STYLE}</style>")?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<table>")?;
+
+    write!(out, "<tr><th></th>")?;
+    for i in 0..dsm.packages.len() {
+        write!(out, "<th class=\"column\">{}</th>", i + 1)?;
+    }
+    writeln!(out, "</tr>")?;
+
+    let mut kinds = Vec::new();
+    for (row, &idx) in dsm.packages.iter().enumerate() {
+        let pkg = &graph[idx];
+        write!(out, "<tr><th class=\"row\">{}. {}</th>", row + 1, escape_xml(&format!("{pkg:?}")))?;
+
+        for column in 0..dsm.packages.len() {
+            let mut classes = Vec::new();
+            if row == column {
+                classes.push("diagonal");
+            }
+            if dsm.cycles[row].is_some() && dsm.cycles[row] == dsm.cycles[column] {
+                classes.push("partition");
+            }
+
+            let Some(info) = dsm.cells.get(&(row, column)) else {
+                write!(out, "<td class=\"{}\"></td>", classes.join(" "))?;
+                continue;
+            };
+            if column > row {
+                classes.push("cycle");
+            }
+            if !kinds.contains(&info.kind) {
+                kinds.push(info.kind);
+            }
+
+            let dep = &graph[dsm.packages[column]];
+            let style = edge_style(info, config, theme);
+            write!(
+                out,
+                "<td class=\"{}\" style=\"color: {}\" title=\"{} → {}: {}\">{}</td>",
+                classes.join(" "),
+                escape_xml(&style.color),
+                escape_xml(&pkg.name),
+                escape_xml(&dep.name),
+                info.kind,
+                abbreviation(info.kind),
+            )?;
+        }
+        writeln!(out, "</tr>")?;
+    }
+    writeln!(out, "</table>")?;
+
+    writeln!(out, "<ul class=\"legend\">")?;
+    writeln!(out, "<li>Row depends on column</li>")?;
+    for kind in kinds {
+        let style = edge_style(&DepInfo { kind, ..DepInfo::default() }, config, theme);
+        writeln!(
+            out,
+            "<li><span style=\"color: {}\">{}</span> = {kind}</li>",
+            escape_xml(&style.color),
+            abbreviation(kind),
+        )?;
+    }
+    writeln!(out, "<li><span class=\"cycle\">&nbsp;</span> dependency cycle</li>")?;
+    writeln!(out, "</ul>")?;
+
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")
+}
+
+fn abbreviation(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::NORMAL => "N",
+        DepKind::DEV => "D",
+        DepKind::BUILD => "B",
+        DepKind::BUILD_OF_DEV => "BD",
+        DepKind::NORMAL_AND_BUILD => "NB",
+        DepKind::DEV_AND_BUILD => "DB",
+        DepKind::NORMAL_AND_BUILD_OF_DEV => "NBD",
+        DepKind::DEV_AND_BUILD_OF_DEV => "DBD",
+        DepKind::UNKNOWN => "?",
+    }
+}
+
+const STYLE: &str = "
+body { font-family: sans-serif; font-size: 12px; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 2px 4px; }
+th.row { text-align: left; white-space: nowrap; }
+th.column { min-width: 1.5em; }
+td { text-align: center; font-weight: bold; }
+td.diagonal { background: #ddd; }
+td.partition { background: #fdd; }
+.cycle { outline: 2px solid red; outline-offset: -2px; }
+.legend { list-style: none; padding: 0; }
+.legend span { display: inline-block; min-width: 2em; text-align: center; font-weight: bold; }
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    #[test]
+    fn cycles_are_partitioned_and_above_the_diagonal() {
+        // app -> b -> c -> app is a cycle (through a dev-dependency of the workspace member), with
+        // the tail log below it and cli above it
+        let graph = test_graph(
+            &["cli", "app"],
+            &[
+                ("cli", "app", DepKind::NORMAL),
+                ("app", "b", DepKind::NORMAL),
+                ("b", "c", DepKind::NORMAL),
+                ("c", "app", DepKind::DEV),
+                ("c", "log", DepKind::NORMAL),
+            ],
+        );
+        let dsm = Dsm::new(&graph);
+
+        let names: Vec<_> = dsm.packages.iter().map(|&idx| graph[idx].name.as_str()).collect();
+        assert_eq!(names, ["log", "app", "b", "c", "cli"]);
+        assert_eq!(dsm.cycles, [None, Some(0), Some(0), Some(0), None]);
+
+        let mut above_diagonal: Vec<_> =
+            dsm.cells.keys().copied().filter(|(row, column)| column > row).collect();
+        above_diagonal.sort_unstable();
+        assert_eq!(above_diagonal, [(1, 2), (2, 3)]);
+        assert_eq!(dsm.cells.len(), 5);
+        assert_eq!(dsm.cells[&(3, 1)].kind, DepKind::DEV);
+    }
+}
@@ -0,0 +1,109 @@
+use std::io::{self, Write};
+
+use crate::graph::DepGraph;
+
+use super::{
+    escape_xml,
+    properties::{
+        EDGE_PROPERTIES, NODE_PROPERTIES, PropertyType, edge_properties, node_properties,
+    },
+    sorted_edges, sorted_nodes,
+};
+
+/// Writes the graph as GEXF (e.g. for Gephi), with the properties of packages and dependencies as
+/// typed attributes rather than visual styles.
+pub(crate) fn write_gexf(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+    writeln!(out, r#"  <graph defaultedgetype="directed">"#)?;
+
+    write_attributes(out, "node", &NODE_PROPERTIES)?;
+    write_attributes(out, "edge", &EDGE_PROPERTIES)?;
+
+    writeln!(out, "    <nodes>")?;
+    for pkg in sorted_nodes(graph) {
+        writeln!(
+            out,
+            r#"      <node id="{}" label="{}">"#,
+            escape_xml(&pkg.id),
+            escape_xml(&format!("{pkg:?}")),
+        )?;
+        write_values(out, &NODE_PROPERTIES, node_properties(pkg))?;
+        writeln!(out, "      </node>")?;
+    }
+    writeln!(out, "    </nodes>")?;
+
+    writeln!(out, "    <edges>")?;
+    for (i, (source, target, dep)) in sorted_edges(graph).into_iter().enumerate() {
+        // The kind is used as the edge type so Gephi doesn't merge parallel edges of different
+        // kinds
+        writeln!(
+            out,
+            r#"      <edge id="{i}" source="{}" target="{}" kind="{}">"#,
+            escape_xml(&source.id),
+            escape_xml(&target.id),
+            dep.info.kind.as_str(),
+        )?;
+        write_values(out, &EDGE_PROPERTIES, edge_properties(dep))?;
+        writeln!(out, "      </edge>")?;
+    }
+    writeln!(out, "    </edges>")?;
+
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")
+}
+
+fn write_attributes(
+    out: &mut impl Write,
+    class: &str,
+    properties: &[(&str, PropertyType)],
+) -> io::Result<()> {
+    writeln!(out, r#"    <attributes class="{class}">"#)?;
+    for (name, ty) in properties {
+        writeln!(out, r#"      <attribute id="{name}" title="{name}" type="{}"/>"#, ty.as_str())?;
+    }
+    writeln!(out, "    </attributes>")
+}
+
+fn write_values(
+    out: &mut impl Write,
+    properties: &[(&str, PropertyType)],
+    values: Vec<Option<String>>,
+) -> io::Result<()> {
+    writeln!(out, "        <attvalues>")?;
+    for ((name, _), value) in properties.iter().zip(values) {
+        if let Some(value) = value {
+            writeln!(out, r#"          <attvalue for="{name}" value="{}"/>"#, escape_xml(&value))?;
+        }
+    }
+    writeln!(out, "        </attvalues>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dep_info::DepKind, graph::test_graph};
+
+    #[test]
+    fn properties_are_typed_attributes() {
+        let mut graph = test_graph(&["app"], &[("app", "log", DepKind::BUILD)]);
+        for pkg in graph.node_weights_mut().filter(|pkg| pkg.name == "log") {
+            pkg.dep_info.is_optional = true;
+            pkg.dep_info.is_optional_direct = true;
+        }
+        let mut out = Vec::new();
+        write_gexf(&mut out, &graph).unwrap();
+        let gexf = String::from_utf8(out).unwrap();
+
+        let (nodes, edges) = gexf.split_once(r#"<attributes class="edge">"#).unwrap();
+        let attribute =
+            r#"<attribute id="is_optional_direct" title="is_optional_direct" type="boolean"/>"#;
+        assert!(nodes.contains(attribute), "{gexf}");
+        assert!(edges.split("</attributes>").next().unwrap().contains(attribute), "{gexf}");
+
+        let log = gexf.split(r#"<node id="log@0.1.0" label="log">"#).nth(1).unwrap();
+        let log = log.split("</node>").next().unwrap();
+        assert!(log.contains(r#"<attvalue for="is_optional_direct" value="true"/>"#), "{log}");
+        assert!(gexf.contains(r#"source="app@0.1.0" target="log@0.1.0" kind="build">"#), "{gexf}");
+    }
+}
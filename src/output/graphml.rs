@@ -0,0 +1,117 @@
+use std::io::{self, Write};
+
+use crate::graph::DepGraph;
+
+use super::{
+    escape_xml,
+    properties::{
+        EDGE_PROPERTIES, NODE_PROPERTIES, PropertyType, edge_properties, node_properties,
+    },
+    sorted_edges, sorted_nodes,
+};
+
+/// Writes the graph as GraphML (e.g. for yEd or Cytoscape), with the properties of packages and
+/// dependencies as typed data rather than visual styles.
+pub(crate) fn write_graphml(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+
+    // Keys are prefixed since node and edge properties share some names
+    writeln!(out, r#"  <key id="n_label" for="node" attr.name="label" attr.type="string"/>"#)?;
+    write_keys(out, "node", "n", &NODE_PROPERTIES)?;
+    write_keys(out, "edge", "e", &EDGE_PROPERTIES)?;
+
+    writeln!(out, r#"  <graph id="G" edgedefault="directed">"#)?;
+
+    for pkg in sorted_nodes(graph) {
+        writeln!(out, r#"    <node id="{}">"#, escape_xml(&pkg.id))?;
+        write_data(out, "n_label", &format!("{pkg:?}"))?;
+        write_values(out, "n", &NODE_PROPERTIES, node_properties(pkg))?;
+        writeln!(out, "    </node>")?;
+    }
+
+    for (i, (source, target, dep)) in sorted_edges(graph).into_iter().enumerate() {
+        writeln!(
+            out,
+            r#"    <edge id="e{i}" source="{}" target="{}">"#,
+            escape_xml(&source.id),
+            escape_xml(&target.id),
+        )?;
+        write_values(out, "e", &EDGE_PROPERTIES, edge_properties(dep))?;
+        writeln!(out, "    </edge>")?;
+    }
+
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+fn write_keys(
+    out: &mut impl Write,
+    element: &str,
+    prefix: &str,
+    properties: &[(&str, PropertyType)],
+) -> io::Result<()> {
+    for (name, ty) in properties {
+        writeln!(
+            out,
+            r#"  <key id="{prefix}_{name}" for="{element}" attr.name="{name}" attr.type="{}"/>"#,
+            ty.as_str(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn write_values(
+    out: &mut impl Write,
+    prefix: &str,
+    properties: &[(&str, PropertyType)],
+    values: Vec<Option<String>>,
+) -> io::Result<()> {
+    for ((name, _), value) in properties.iter().zip(values) {
+        if let Some(value) = value {
+            write_data(out, &format!("{prefix}_{name}"), &value)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_data(out: &mut impl Write, key: &str, value: &str) -> io::Result<()> {
+    writeln!(out, r#"      <data key="{key}">{}</data>"#, escape_xml(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dep_info::DepKind, graph::test_graph};
+
+    #[test]
+    fn properties_are_typed_keys() {
+        let mut graph = test_graph(&["app"], &[("app", "log", DepKind::NORMAL)]);
+        for pkg in graph.node_weights_mut().filter(|pkg| pkg.name == "log") {
+            pkg.dep_info.is_optional = true;
+            pkg.dep_info.is_optional_direct = true;
+        }
+        let mut out = Vec::new();
+        write_graphml(&mut out, &graph).unwrap();
+        let graphml = String::from_utf8(out).unwrap();
+
+        for key in [
+            r#"<key id="n_is_optional_direct" for="node" attr.name="is_optional_direct" attr.type="boolean"/>"#,
+            r#"<key id="n_version" for="node" attr.name="version" attr.type="string"/>"#,
+            r#"<key id="e_is_optional_direct" for="edge" attr.name="is_optional_direct" attr.type="boolean"/>"#,
+            r#"<key id="e_platform" for="edge" attr.name="platform" attr.type="string"/>"#,
+        ] {
+            assert!(graphml.contains(key), "{key} missing in {graphml}");
+        }
+
+        let log = graphml.split(r#"<node id="log@0.1.0">"#).nth(1).unwrap();
+        let log = log.split("</node>").next().unwrap();
+        assert!(log.contains(r#"<data key="n_is_optional_direct">true</data>"#), "{log}");
+        assert!(log.contains(r#"<data key="n_kind">normal</data>"#), "{log}");
+        // Properties without a value, like the platform of an edge that isn't target-specific,
+        // are left out
+        assert!(!graphml.contains(r#"<data key="e_platform">"#), "{graphml}");
+    }
+}
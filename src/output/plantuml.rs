@@ -0,0 +1,163 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use crate::{cli::Config, graph::DepGraph};
+
+use super::{
+    edge_label, sorted_edges, sorted_nodes,
+    style::{ArrowHead, LineStyle, Shape, background_color, edge_style, node_style},
+    theme::Theme,
+};
+
+/// Writes the graph as a PlantUML diagram, with the same shapes, colors and line styles as the DOT
+/// output as far as PlantUML supports them.
+pub(crate) fn write_plantuml(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    writeln!(out, "@startuml")?;
+    // PlantUML can't lay out graphs from bottom to top or right to left
+    if matches!(config.rankdir.as_deref(), Some("LR" | "RL")) {
+        writeln!(out, "left to right direction")?;
+    }
+    if let Some(bgcolor) = background_color(theme) {
+        writeln!(out, "skinparam backgroundColor {}", color(&bgcolor))?;
+    }
+
+    // Package IDs aren't valid PlantUML identifiers, so nodes are numbered instead
+    let mut aliases = HashMap::new();
+    for (i, pkg) in sorted_nodes(graph).into_iter().enumerate() {
+        let style = node_style(pkg, config, theme);
+        let element = match style.shape {
+            Shape::Ellipse => "usecase",
+            Shape::Box => "rectangle",
+            Shape::Note => "file",
+            Shape::Hexagon => "hexagon",
+            // There is no octagon, a 3D box still sets git dependencies apart
+            Shape::Octagon => "node",
+            Shape::Folder => "folder",
+            Shape::Point => "circle",
+        };
+
+        let mut spec = Vec::new();
+        if let Some(fill_color) = &style.fill_color {
+            spec.push(color_value(fill_color).to_owned());
+        }
+        spec.push(format!("line:{}", color_value(&style.color)));
+        match style.line {
+            LineStyle::Solid | LineStyle::Invisible => {}
+            LineStyle::Dashed => spec.push("line.dashed".to_owned()),
+            LineStyle::Dotted => spec.push("line.dotted".to_owned()),
+        }
+        // There are no double borders, use a bold line instead
+        if style.pen_width >= 2.0 || style.double_border {
+            spec.push("line.bold".to_owned());
+        }
+        spec.push(format!("text:{}", color_value(&style.font_color)));
+
+        let label = format!("{pkg:?}").replace('"', "'");
+        writeln!(out, "{element} \"{label}\" as n{i} #{}", spec.join(";"))?;
+        aliases.insert(&pkg.id, i);
+    }
+
+    for (source, target, dep) in sorted_edges(graph) {
+        let style = edge_style(&dep.info, config, theme);
+
+        let mut spec = vec![color(&style.color)];
+        match style.line {
+            LineStyle::Solid => {}
+            LineStyle::Dashed => spec.push("dashed".to_owned()),
+            LineStyle::Dotted => spec.push("dotted".to_owned()),
+            LineStyle::Invisible => spec.push("hidden".to_owned()),
+        }
+        if style.pen_width != 1.0 {
+            spec.push(format!("thickness={}", style.pen_width));
+        }
+        let head = match style.arrow_head {
+            ArrowHead::Normal => ">",
+            ArrowHead::Empty => "|>",
+            ArrowHead::None => "",
+        };
+
+        write!(
+            out,
+            "n{} -[{}]-{head} n{}",
+            aliases[&source.id],
+            spec.join(","),
+            aliases[&target.id],
+        )?;
+        let label = edge_label(dep, target, config);
+        if !label.is_empty() {
+            let font_color = color(&style.font_color);
+            let label: Vec<_> =
+                label.iter().map(|line| format!("<color:{font_color}>{line}</color>")).collect();
+            write!(out, " : {}", label.join("\\n"))?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out, "@enduml")
+}
+
+/// A color as used by PlantUML, e.g. `#blue` or `#00CD00`.
+fn color(color: &str) -> String {
+    format!("#{}", color_value(color))
+}
+
+/// A color without the leading `#`, as used in the inline styles of elements.
+fn color_value(color: &str) -> &str {
+    color.strip_prefix('#').unwrap_or(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cli::parse_view_options, dep_info::DepKind, graph::test_graph, package::PackageSource,
+    };
+
+    #[test]
+    fn styles_follow_the_theme() {
+        let mut graph = test_graph(
+            &["app"],
+            &[("app", "git-dep", DepKind::DEV), ("app", "registry-dep", DepKind::NORMAL)],
+        );
+        for pkg in graph.node_weights_mut() {
+            match pkg.name.as_str() {
+                "git-dep" => pkg.source = PackageSource::Git("https://example.com/git-dep".into()),
+                "registry-dep" => {
+                    pkg.source = PackageSource::Registry("https://example.com/index".into());
+                    pkg.is_proc_macro = true;
+                    pkg.dep_info.is_target_dep = true;
+                }
+                _ => {}
+            }
+        }
+        for dep in graph.edge_weights_mut() {
+            dep.info.is_target_dep = dep.info.kind == DepKind::NORMAL;
+            dep.info.is_optional = dep.info.kind == DepKind::DEV;
+        }
+        let config = parse_view_options(&["--dev-deps".to_owned()]).unwrap();
+        let mut out = Vec::new();
+        write_plantuml(&mut out, &graph, &config, &Theme::builtin("default").unwrap()).unwrap();
+        let plantuml = String::from_utf8(out).unwrap();
+
+        assert_eq!(
+            plantuml.lines().collect::<Vec<_>>(),
+            [
+                "@startuml",
+                r#"rectangle "app" as n0 #line:black;text:black"#,
+                r#"node "git-dep" as n1 #line:blue;text:black"#,
+                // There are no double borders for proc-macros
+                r#"hexagon "registry-dep" as n2 #lightgrey;line:black;line.bold;text:black"#,
+                "n0 -[#blue,dashed]-> n1",
+                "n0 -[#black]-|> n2",
+                "@enduml",
+            ]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    dep_info::Dependency,
+    package::{Package, PackageSource},
+};
+
+/// The type of a property, using the names that both GraphML and GEXF use.
+#[derive(Clone, Copy)]
+pub(super) enum PropertyType {
+    String,
+    Boolean,
+}
+
+impl PropertyType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Boolean => "boolean",
+        }
+    }
+}
+
+/// The properties that `node_properties` returns values for.
+pub(super) const NODE_PROPERTIES: [(&str, PropertyType); 11] = [
+    ("name", PropertyType::String),
+    ("version", PropertyType::String),
+    ("source", PropertyType::String),
+    ("is_ws_member", PropertyType::Boolean),
+    ("is_proc_macro", PropertyType::Boolean),
+    ("kind", PropertyType::String),
+    ("host", PropertyType::String),
+    ("target", PropertyType::String),
+    ("is_optional", PropertyType::Boolean),
+    ("is_optional_direct", PropertyType::Boolean),
+    ("is_target_dep", PropertyType::Boolean),
+];
+
+/// The properties that `edge_properties` returns values for.
+pub(super) const EDGE_PROPERTIES: [(&str, PropertyType); 7] = [
+    ("kind", PropertyType::String),
+    ("host", PropertyType::String),
+    ("target", PropertyType::String),
+    ("is_optional", PropertyType::Boolean),
+    ("is_optional_direct", PropertyType::Boolean),
+    ("is_target_dep", PropertyType::Boolean),
+    ("platform", PropertyType::String),
+];
+
+/// The values of a package's properties, in the order of `NODE_PROPERTIES`.
+///
+/// `kind`, `host` and `target` describe how the package is used by the workspace members: `host`
+/// and `target` say whether it is built for the host (i.e. used by build scripts and proc-macros)
+/// or the target platform, always or only for tests. `is_optional_direct` is only true if all
+/// dependents declare the package as optional, rather than it only being used by optional ones.
+pub(super) fn node_properties(pkg: &Package) -> Vec<Option<String>> {
+    let info = &pkg.dep_info;
+    let source = match &pkg.source {
+        PackageSource::CratesIo => Some("crates-io".to_owned()),
+        PackageSource::Registry(url) => Some(format!("registry+{url}")),
+        PackageSource::Git(url) => Some(format!("git+{url}")),
+        PackageSource::Path => {
+            Some(pkg.path.as_ref().map_or("path".to_owned(), |p| format!("path+{p}")))
+        }
+        PackageSource::Placeholder(_) => None,
+    };
+
+    vec![
+        Some(pkg.name.clone()),
+        (!matches!(pkg.source, PackageSource::Placeholder(_))).then(|| pkg.version.to_string()),
+        source,
+        Some(pkg.is_ws_member.to_string()),
+        Some(pkg.is_proc_macro.to_string()),
+        Some(info.kind.as_str().to_owned()),
+        Some(info.kind.host.as_str().to_owned()),
+        Some(info.kind.target.as_str().to_owned()),
+        Some(info.is_optional.to_string()),
+        Some(info.is_optional_direct.to_string()),
+        Some(info.is_target_dep.to_string()),
+    ]
+}
+
+/// The values of a dependency's properties, in the order of `EDGE_PROPERTIES`. `platform` is the
+/// target triple or `cfg()` expression for target-specific dependencies.
+pub(super) fn edge_properties(dep: &Dependency) -> Vec<Option<String>> {
+    let info = &dep.info;
+    vec![
+        Some(info.kind.as_str().to_owned()),
+        Some(info.kind.host.as_str().to_owned()),
+        Some(info.kind.target.as_str().to_owned()),
+        Some(info.is_optional.to_string()),
+        Some(info.is_optional_direct.to_string()),
+        Some(info.is_target_dep.to_string()),
+        dep.target.clone(),
+    ]
+}
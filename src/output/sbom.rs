@@ -0,0 +1,96 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    dep_info::{BuildFlag, DepInfo, DepKind},
+    package::{Package, PackageSource},
+};
+
+use super::percent_encode;
+
+/// Whether a package or dependency ends up in what is built from the workspace, in the terms that
+/// both CycloneDX and SPDX use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Scope {
+    Required,
+    Optional,
+    /// only used for building or testing, like build-, dev- and proc-macro dependencies
+    Excluded,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Required => "required",
+            Self::Optional => "optional",
+            Self::Excluded => "excluded",
+        }
+    }
+}
+
+/// The scope of a package or dependency, or `None` if its kind is unknown.
+pub(super) fn scope(info: &DepInfo) -> Option<Scope> {
+    if info.kind == DepKind::UNKNOWN {
+        None
+    } else if info.kind.target != BuildFlag::Always {
+        Some(Scope::Excluded)
+    } else if info.is_optional {
+        Some(Scope::Optional)
+    } else {
+        Some(Scope::Required)
+    }
+}
+
+/// The package URL (https://github.com/package-url/purl-spec) of a package, unless it comes from
+/// a local path.
+pub(super) fn purl(pkg: &Package) -> Option<String> {
+    let purl = format!("pkg:cargo/{}@{}", pkg.name, pkg.version);
+    match &pkg.source {
+        PackageSource::CratesIo => Some(purl),
+        PackageSource::Registry(url) => {
+            Some(format!("{purl}?repository_url={}", percent_encode(url)))
+        }
+        PackageSource::Git(url) => {
+            // `url` is the repository URL, followed by the requested ref and the commit hash
+            let (url, commit) = url.split_once('#').unwrap_or((url, ""));
+            let url = url.split_once('?').map_or(url, |(url, _)| url);
+            let vcs_url = if commit.is_empty() {
+                format!("git+{url}")
+            } else {
+                format!("git+{url}@{commit}")
+            };
+            Some(format!("{purl}?vcs_url={}", percent_encode(&vcs_url)))
+        }
+        PackageSource::Path | PackageSource::Placeholder(_) => None,
+    }
+}
+
+/// Packages that are part of the SBOM, i.e. all except placeholders for pruned dependencies.
+pub(super) fn is_included(pkg: &Package) -> bool {
+    !matches!(pkg.source, PackageSource::Placeholder(_))
+}
+
+/// The current time as an ISO 8601 timestamp in UTC, like `2024-01-31T12:00:00Z`.
+pub(super) fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // Conversion from days since the epoch to a date, from
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+    )
+}
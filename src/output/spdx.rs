@@ -0,0 +1,201 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    io::{self, Write},
+};
+
+use serde_json::{Value, json};
+
+use crate::{
+    dep_info::BuildFlag,
+    graph::DepGraph,
+    package::{Package, PackageSource},
+};
+
+use super::{
+    describe,
+    sbom::{Scope, is_included, purl, scope, timestamp},
+    sorted_edges, sorted_nodes,
+};
+
+/// Writes the graph as an SPDX 2.3 document in JSON format. Dependencies are expressed as
+/// relationships whose type depends on the dependency's scope, e.g. `DEPENDS_ON` for required
+/// dependencies and `DEV_DEPENDENCY_OF` for ones only used by tests.
+pub(crate) fn write_spdx(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    let packages: Vec<_> = sorted_nodes(graph).into_iter().filter(|pkg| is_included(pkg)).collect();
+    let ws_members: Vec<_> = packages.iter().filter(|pkg| pkg.is_ws_member).collect();
+    let ids = spdx_ids(&packages);
+
+    let name = match ws_members[..] {
+        [] => "dependencies".to_owned(),
+        _ => ws_members.iter().map(|pkg| pkg.name.as_str()).collect::<Vec<_>>().join(", "),
+    };
+    let created = timestamp();
+
+    // The namespace has to be unique for every document
+    let mut hasher = DefaultHasher::new();
+    created.hash(&mut hasher);
+    for pkg in &packages {
+        pkg.id.hash(&mut hasher);
+    }
+    let namespace = format!(
+        "https://spdx.org/spdxdocs/{}-{:016x}",
+        spdx_id_chars(&name.replace(", ", "-")),
+        hasher.finish(),
+    );
+
+    let mut relationships: Vec<_> = ws_members
+        .iter()
+        .map(|pkg| ("SPDXRef-DOCUMENT", "DESCRIBES", ids[pkg.id.as_str()].as_str()))
+        .collect();
+    // Parallel edges of the same scope result in the same relationship
+    let mut dep_relationships = BTreeSet::new();
+    for (source, target, dep) in sorted_edges(graph) {
+        if !is_included(source) || !is_included(target) {
+            continue;
+        }
+
+        let (source, target) = (ids[source.id.as_str()].as_str(), ids[target.id.as_str()].as_str());
+        let relationship = match scope(&dep.info) {
+            Some(Scope::Required) | None => (source, "DEPENDS_ON", target),
+            Some(Scope::Optional) => (target, "OPTIONAL_DEPENDENCY_OF", source),
+            Some(Scope::Excluded) if dep.info.kind.host == BuildFlag::Always => {
+                (target, "BUILD_DEPENDENCY_OF", source)
+            }
+            Some(Scope::Excluded) => (target, "DEV_DEPENDENCY_OF", source),
+        };
+        dep_relationships.insert(relationship);
+    }
+    relationships.extend(dep_relationships);
+
+    let document = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": namespace,
+        "creationInfo": {
+            "created": created,
+            "creators": [format!("Tool: {}-{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages.iter().map(|pkg| package(pkg, &ids[pkg.id.as_str()])).collect::<Vec<_>>(),
+        "relationships": relationships
+            .into_iter()
+            .map(|(element, ty, related)| json!({
+                "spdxElementId": element,
+                "relationshipType": ty,
+                "relatedSpdxElement": related,
+            }))
+            .collect::<Vec<_>>(),
+    });
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out)
+}
+
+fn package(pkg: &Package, spdx_id: &str) -> Value {
+    let download_location = match &pkg.source {
+        PackageSource::CratesIo => {
+            format!("https://crates.io/api/v1/crates/{}/{}/download", pkg.name, pkg.version)
+        }
+        PackageSource::Git(url) => format!("git+{url}"),
+        PackageSource::Registry(_) | PackageSource::Path | PackageSource::Placeholder(_) => {
+            "NOASSERTION".to_owned()
+        }
+    };
+
+    let info = &pkg.dep_info;
+    let mut package = json!({
+        "SPDXID": spdx_id,
+        "name": pkg.name,
+        "versionInfo": pkg.version.to_string(),
+        "downloadLocation": download_location,
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+        "comment": describe(info.kind, info.is_target_dep, info.is_optional.then_some("optional")),
+    });
+    if let Some(purl) = purl(pkg) {
+        package["externalRefs"] = json!([{
+            "referenceCategory": "PACKAGE-MANAGER",
+            "referenceType": "purl",
+            "referenceLocator": purl,
+        }]);
+    }
+
+    package
+}
+
+/// Assigns SPDX identifiers to the packages, derived from their IDs. Replacing the characters
+/// that aren't allowed can make them ambiguous, so a number is appended to repeated identifiers.
+fn spdx_ids<'a>(packages: &[&'a Package]) -> HashMap<&'a str, String> {
+    let mut used = HashSet::new();
+    packages
+        .iter()
+        .map(|pkg| {
+            let base = format!("SPDXRef-Package-{}", spdx_id_chars(&pkg.id));
+            let mut id = base.clone();
+            let mut i = 1;
+            while !used.insert(id.clone()) {
+                i += 1;
+                id = format!("{base}-{i}");
+            }
+            (pkg.id.as_str(), id)
+        })
+        .collect()
+}
+
+/// Replaces characters that aren't allowed in SPDX identifiers.
+fn spdx_id_chars(s: &str) -> String {
+    s.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dep_info::DepKind, graph::test_graph, util::set_name_stats};
+
+    #[test]
+    fn spdx_ids_are_unique() {
+        // The last one is what the second one would become with a number appended
+        let mut packages =
+            [Package::test("a_b", false), Package::test("a-b", false), Package::test("a-b", false)];
+        packages[2].id = "a-b@0.1.0-2".to_owned();
+        let packages: Vec<_> = packages.iter().collect();
+
+        let ids = spdx_ids(&packages);
+        assert_eq!(ids["a_b@0.1.0"], "SPDXRef-Package-a-b-0.1.0");
+        assert_eq!(ids["a-b@0.1.0"], "SPDXRef-Package-a-b-0.1.0-2");
+        assert_eq!(ids["a-b@0.1.0-2"], "SPDXRef-Package-a-b-0.1.0-2-2");
+        assert_eq!(ids.values().collect::<HashSet<_>>().len(), 3);
+    }
+
+    #[test]
+    fn relationships_are_unique() {
+        let mut graph = test_graph(
+            &["app"],
+            &[
+                ("app", "util", DepKind::NORMAL),
+                ("app", "util", DepKind::DEV),
+                ("app", "util", DepKind::NORMAL),
+                ("app", "a_b", DepKind::NORMAL),
+                ("app", "a-b", DepKind::NORMAL),
+            ],
+        );
+        set_name_stats(&mut graph);
+
+        let mut out = Vec::new();
+        write_spdx(&mut out, &graph).unwrap();
+        let document: Value = serde_json::from_slice(&out).unwrap();
+
+        let packages = document["packages"].as_array().unwrap();
+        let ids: HashSet<_> = packages.iter().map(|pkg| pkg["SPDXID"].as_str().unwrap()).collect();
+        assert_eq!(ids.len(), 4);
+
+        let relationships = document["relationships"].as_array().unwrap();
+        let unique: HashSet<_> = relationships.iter().map(Value::to_string).collect();
+        assert_eq!(unique.len(), relationships.len());
+        // DESCRIBES app, app DEPENDS_ON a_b, a-b and util, util DEV_DEPENDENCY_OF app
+        assert_eq!(relationships.len(), 5);
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::{HashMap, VecDeque, hash_map::Entry as HashMapEntry};
+
+use petgraph::{Direction, stable_graph::NodeIndex, visit::EdgeRef};
+use rusqlite::{Connection, Transaction, params, params_from_iter, types::Value};
+
+use crate::{dep_info::DepKind, graph::DepGraph};
+
+use super::{
+    properties::{
+        EDGE_PROPERTIES, NODE_PROPERTIES, PropertyType, edge_properties, node_properties,
+    },
+    sorted_edges, sorted_nodes,
+};
+
+/// Writes the graph to a new SQLite database with the tables
+///
+/// - `packages`: one row per package, with the same columns as the GraphML output and `id` as the
+///   primary key
+/// - `edges`: one row per dependency, referring to packages through `from_id` and `to_id`
+/// - `features`: the features that are enabled for each package
+/// - `closure`: one row for every package and each of its direct and transitive dependencies,
+///   with the shortest `distance` between them and the `kind` of the dependency as seen from the
+///   dependent
+pub(crate) fn write_sqlite(conn: &mut Connection, graph: &DepGraph) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+
+    create_table(&tx, "packages", &[("id", "TEXT PRIMARY KEY")], &NODE_PROPERTIES, &[])?;
+    create_table(
+        &tx,
+        "edges",
+        &[
+            ("from_id", "TEXT NOT NULL REFERENCES packages"),
+            ("to_id", "TEXT NOT NULL REFERENCES packages"),
+        ],
+        &EDGE_PROPERTIES,
+        &[("rename", "TEXT"), ("req", "TEXT"), ("uses_default_features", "INTEGER")],
+    )?;
+    tx.execute_batch(
+        "CREATE TABLE features (
+            package_id TEXT NOT NULL REFERENCES packages,
+            feature TEXT NOT NULL,
+            PRIMARY KEY (package_id, feature)
+        );
+        CREATE TABLE closure (
+            ancestor_id TEXT NOT NULL REFERENCES packages,
+            descendant_id TEXT NOT NULL REFERENCES packages,
+            distance INTEGER NOT NULL,
+            kind TEXT NOT NULL,
+            PRIMARY KEY (ancestor_id, descendant_id)
+        );
+        CREATE INDEX edges_to_id ON edges (to_id);
+        CREATE INDEX closure_descendant_id ON closure (descendant_id);",
+    )?;
+
+    {
+        let mut insert_package =
+            tx.prepare(&insert_statement("packages", 1 + NODE_PROPERTIES.len()))?;
+        let mut insert_feature = tx.prepare("INSERT INTO features VALUES (?1, ?2)")?;
+        for pkg in sorted_nodes(graph) {
+            let values = node_properties(pkg);
+            let values = NODE_PROPERTIES.iter().zip(values).map(|((_, ty), v)| sql_value(*ty, v));
+            insert_package.execute(params_from_iter(
+                [Value::Text(pkg.id.clone())].into_iter().chain(values),
+            ))?;
+
+            for feature in &pkg.features {
+                insert_feature.execute(params![pkg.id, feature])?;
+            }
+        }
+
+        let mut insert_edge =
+            tx.prepare(&insert_statement("edges", 2 + EDGE_PROPERTIES.len() + 3))?;
+        for (source, target, dep) in sorted_edges(graph) {
+            let ids = [Value::Text(source.id.clone()), Value::Text(target.id.clone())];
+            let values = edge_properties(dep);
+            let values = EDGE_PROPERTIES.iter().zip(values).map(|((_, ty), v)| sql_value(*ty, v));
+            let extra = [
+                dep.rename.clone().map_or(Value::Null, Value::Text),
+                dep.req.as_ref().map_or(Value::Null, |req| Value::Text(req.to_string())),
+                Value::Integer(dep.uses_default_features.into()),
+            ];
+            insert_edge.execute(params_from_iter(ids.into_iter().chain(values).chain(extra)))?;
+        }
+
+        let mut insert_closure = tx.prepare("INSERT INTO closure VALUES (?1, ?2, ?3, ?4)")?;
+        for idx in graph.node_indices() {
+            for (dep_idx, (distance, kind)) in transitive_deps(graph, idx) {
+                insert_closure.execute(params![
+                    graph[idx].id,
+                    graph[dep_idx].id,
+                    distance,
+                    kind.as_str(),
+                ])?;
+            }
+        }
+    }
+
+    tx.commit()
+}
+
+/// Finds all direct and transitive dependencies of a package, along with their distance from it
+/// and their kind as seen from the package (i.e. as if it was the only workspace member).
+fn transitive_deps(
+    graph: &DepGraph,
+    start: NodeIndex<u16>,
+) -> HashMap<NodeIndex<u16>, (u32, DepKind)> {
+    let mut deps: HashMap<_, (u32, DepKind)> = HashMap::new();
+    let mut visit_queue = VecDeque::from([(start, 0, DepKind::NORMAL)]);
+
+    // Packages are revisited whenever their kind changes. Combining kinds only ever adds to them,
+    // so this terminates.
+    while let Some((idx, distance, kind)) = visit_queue.pop_front() {
+        for edge in graph.edges_directed(idx, Direction::Outgoing) {
+            let dep_idx = edge.target();
+            if dep_idx == start {
+                continue;
+            }
+
+            // The edge's kind from the graph already includes the kinds of the workspace members
+            let mut dep_kind = edge.weight().declared_kind;
+            dep_kind.update_outgoing(kind);
+
+            match deps.entry(dep_idx) {
+                HashMapEntry::Vacant(v) => {
+                    v.insert((distance + 1, dep_kind));
+                }
+                HashMapEntry::Occupied(mut o) => {
+                    let (_, old_kind) = o.get_mut();
+                    let mut new_kind = *old_kind;
+                    new_kind.combine_incoming(dep_kind);
+                    if new_kind == *old_kind {
+                        continue;
+                    }
+                    *old_kind = new_kind;
+                }
+            }
+
+            visit_queue.push_back((dep_idx, distance + 1, deps[&dep_idx].1));
+        }
+    }
+
+    deps
+}
+
+fn create_table(
+    tx: &Transaction<'_>,
+    name: &str,
+    keys: &[(&str, &str)],
+    properties: &[(&str, PropertyType)],
+    extra: &[(&str, &str)],
+) -> rusqlite::Result<()> {
+    let properties = properties.iter().map(|(name, ty)| {
+        let ty = match ty {
+            PropertyType::String => "TEXT",
+            PropertyType::Boolean => "INTEGER",
+        };
+        (*name, ty)
+    });
+    let columns: Vec<_> = keys
+        .iter()
+        .copied()
+        .chain(properties)
+        .chain(extra.iter().copied())
+        .map(|(name, ty)| format!("\"{name}\" {ty}"))
+        .collect();
+
+    tx.execute_batch(&format!("CREATE TABLE {name} ({});", columns.join(", ")))
+}
+
+fn insert_statement(table: &str, num_columns: usize) -> String {
+    let placeholders: Vec<_> = (1..=num_columns).map(|i| format!("?{i}")).collect();
+    format!("INSERT INTO {table} VALUES ({})", placeholders.join(", "))
+}
+
+/// Converts a property value to SQL, with booleans as 0 / 1.
+fn sql_value(ty: PropertyType, value: Option<String>) -> Value {
+    match (ty, value) {
+        (_, None) => Value::Null,
+        (PropertyType::String, Some(value)) => Value::Text(value),
+        (PropertyType::Boolean, Some(value)) => Value::Integer((value == "true").into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    fn closure(graph: &DepGraph, from: &str) -> Vec<(String, u32, &'static str)> {
+        let start = graph.node_indices().find(|&idx| graph[idx].name == from).unwrap();
+        let mut deps: Vec<_> = transitive_deps(graph, start)
+            .into_iter()
+            .map(|(idx, (distance, kind))| (graph[idx].name.clone(), distance, kind.as_str()))
+            .collect();
+        deps.sort();
+        deps
+    }
+
+    #[test]
+    fn closure_kinds_are_relative_to_the_dependent() {
+        let graph =
+            test_graph(&["app"], &[("app", "mid", DepKind::DEV), ("mid", "leaf", DepKind::NORMAL)]);
+
+        assert_eq!(
+            closure(&graph, "app"),
+            [("leaf".to_owned(), 2, "dev"), ("mid".to_owned(), 1, "dev")]
+        );
+        assert_eq!(closure(&graph, "mid"), [("leaf".to_owned(), 1, "normal")]);
+    }
+
+    #[test]
+    fn closure_combines_kinds_and_keeps_the_shortest_distance() {
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "build-helper", DepKind::BUILD),
+                ("build-helper", "leaf", DepKind::NORMAL),
+                ("app", "mid", DepKind::NORMAL),
+                ("mid", "lib", DepKind::NORMAL),
+                ("lib", "leaf", DepKind::NORMAL),
+            ],
+        );
+
+        let leaf = closure(&graph, "app").into_iter().find(|(name, ..)| name == "leaf").unwrap();
+        assert_eq!(leaf, ("leaf".to_owned(), 2, "normal-and-build"));
+    }
+}
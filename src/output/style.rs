@@ -0,0 +1,231 @@
+use crate::{
+    cli::Config,
+    dep_info::DepInfo,
+    package::{Package, PackageSource},
+};
+
+use super::theme::{Attrs, Theme};
+
+/// Colors used by the built-in themes that are X11 color names supported by Graphviz, but not
+/// CSS color names. Other color names are passed on as-is.
+const X11_COLORS: [(&str, &str); 3] =
+    [("green3", "#00CD00"), ("turquoise3", "#00C5CD"), ("turquoise4", "#00868B")];
+
+/// Colors for the members of different workspaces with --cluster-workspaces, chosen to be
+/// distinguishable on both light and dark backgrounds.
+pub(super) const WORKSPACE_COLORS: [&str; 8] =
+    ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728", "#9467bd", "#8c564b", "#e377c2", "#17becf"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Shape {
+    Ellipse,
+    Box,
+    Note,
+    Hexagon,
+    Octagon,
+    Folder,
+    Point,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum LineStyle {
+    Solid,
+    Dashed,
+    Dotted,
+    Invisible,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum ArrowHead {
+    Normal,
+    /// an arrow head that is only outlined
+    Empty,
+    None,
+}
+
+/// How a package's node is drawn.
+#[derive(Debug)]
+pub(super) struct NodeStyle {
+    pub shape: Shape,
+    /// the color of the border
+    pub color: String,
+    /// the background color, if the node is filled
+    pub fill_color: Option<String>,
+    pub font_color: String,
+    pub line: LineStyle,
+    pub pen_width: f64,
+    pub double_border: bool,
+}
+
+/// How a dependency edge is drawn.
+#[derive(Debug)]
+pub(super) struct EdgeStyle {
+    pub color: String,
+    pub font_color: String,
+    pub line: LineStyle,
+    pub pen_width: f64,
+    pub arrow_head: ArrowHead,
+}
+
+pub(super) fn node_style(pkg: &Package, config: &Config, theme: &Theme) -> NodeStyle {
+    let mut attrs = theme.node.clone();
+    attrs.extend(&package_attrs(pkg, config, theme));
+
+    let shape = match attrs.get("shape") {
+        Some("box" | "rect" | "rectangle" | "square") => Shape::Box,
+        Some("note") => Shape::Note,
+        Some("hexagon") => Shape::Hexagon,
+        Some("octagon") => Shape::Octagon,
+        Some("folder") => Shape::Folder,
+        Some("point") => Shape::Point,
+        _ => Shape::Ellipse,
+    };
+
+    let (line, pen_width, is_filled) = line_style(&attrs);
+    let color = color(attrs.get("color").unwrap_or("black"));
+    let fill_color = is_filled.then(|| match attrs.get("fillcolor") {
+        Some(fill_color) => self::color(fill_color),
+        // Like Graphviz, fall back to the border color
+        None if attrs.get("color").is_some() => color.clone(),
+        None => "lightgrey".to_owned(),
+    });
+
+    NodeStyle {
+        shape,
+        color,
+        fill_color,
+        font_color: self::color(attrs.get("fontcolor").unwrap_or("black")),
+        line,
+        pen_width,
+        double_border: attrs.get("peripheries").and_then(|p| p.parse::<u32>().ok()) >= Some(2),
+    }
+}
+
+pub(super) fn edge_style(info: &DepInfo, config: &Config, theme: &Theme) -> EdgeStyle {
+    let mut attrs = theme.edge.clone();
+    attrs.extend(&edge_attrs(info, config, theme));
+
+    let (line, pen_width, _) = line_style(&attrs);
+    let arrow_head = match attrs.get("arrowhead") {
+        Some("empty" | "onormal") => ArrowHead::Empty,
+        Some("none") => ArrowHead::None,
+        _ => ArrowHead::Normal,
+    };
+
+    EdgeStyle {
+        color: color(attrs.get("color").unwrap_or("black")),
+        font_color: color(attrs.get("fontcolor").unwrap_or("black")),
+        line,
+        pen_width,
+        arrow_head,
+    }
+}
+
+pub(super) fn background_color(theme: &Theme) -> Option<String> {
+    theme.graph.get("bgcolor").map(color)
+}
+
+/// The line style, pen width and whether the element is filled, from the `style` and `penwidth`
+/// attributes. Styles that only some output formats could draw, like `diagonals`, are ignored.
+fn line_style(attrs: &Attrs) -> (LineStyle, f64, bool) {
+    let mut line = LineStyle::Solid;
+    let mut pen_width = attrs.get("penwidth").and_then(|w| w.parse().ok()).unwrap_or(1.0);
+    let mut is_filled = false;
+
+    for part in attrs.get("style").unwrap_or_default().split(',') {
+        match part.trim() {
+            "dashed" => line = LineStyle::Dashed,
+            "dotted" => line = LineStyle::Dotted,
+            "invis" => line = LineStyle::Invisible,
+            "bold" => pen_width = f64::max(pen_width, 2.0),
+            "filled" => is_filled = true,
+            _ => {}
+        }
+    }
+
+    (line, pen_width, is_filled)
+}
+
+/// A color as a hex value or CSS color name.
+fn color(name: &str) -> String {
+    match X11_COLORS.iter().find(|(x11_name, _)| x11_name.eq_ignore_ascii_case(name)) {
+        Some((_, hex)) => (*hex).to_owned(),
+        None => name.to_owned(),
+    }
+}
+
+/// The theme attributes for a package's node, not including the defaults for all nodes.
+pub(super) fn package_attrs(pkg: &Package, config: &Config, theme: &Theme) -> Attrs {
+    let mut attrs = Attrs::new();
+    if let Some((shape, _)) = node_shape(pkg, theme) {
+        attrs.extend(shape);
+    }
+    if pkg.is_proc_macro {
+        attrs.extend(&theme.node_state.proc_macro);
+    }
+    attrs.extend(&node_attrs(&pkg.dep_info, config, theme));
+    if config.cluster_workspaces
+        && let Some(workspace) = pkg.workspace
+    {
+        attrs.set("color", workspace_color(workspace));
+    }
+
+    attrs
+}
+
+pub(super) fn workspace_color(workspace: usize) -> &'static str {
+    WORKSPACE_COLORS[workspace % WORKSPACE_COLORS.len()]
+}
+
+pub(super) fn edge_attrs(dep: &DepInfo, config: &Config, theme: &Theme) -> Attrs {
+    let mut attrs = theme.kind.get(dep.kind).clone();
+
+    if dep.is_target_dep {
+        attrs.extend(&theme.edge_state.target);
+    }
+
+    if dep.is_optional_direct {
+        attrs.extend(&theme.edge_state.optional);
+    } else if dep.is_optional {
+        attrs.extend(&theme.edge_state.transitively_optional);
+    }
+
+    if dep.is_on_path && config.highlight_paths {
+        attrs.extend(&theme.edge_state.highlighted);
+    }
+
+    attrs
+}
+
+pub(super) fn node_attrs(info: &DepInfo, config: &Config, theme: &Theme) -> Attrs {
+    let mut attrs = theme.kind.get(info.kind).clone();
+
+    if info.is_on_path && config.highlight_paths {
+        attrs.extend(&theme.node_state.highlighted);
+    }
+
+    if info.is_optional {
+        attrs.extend(&theme.node_state.optional);
+    }
+
+    if info.is_target_dep {
+        attrs.extend(&theme.node_state.target);
+    }
+
+    attrs
+}
+
+/// The shape-related attributes for a package's node, along with a description for the legend.
+pub(super) fn node_shape<'a>(pkg: &Package, theme: &'a Theme) -> Option<(&'a Attrs, &'static str)> {
+    let states = &theme.node_state;
+    if pkg.is_ws_member {
+        return Some((&states.workspace_member, "workspace member"));
+    }
+    match pkg.source {
+        PackageSource::CratesIo => None,
+        PackageSource::Registry(_) => Some((&states.alt_registry, "alternate registry")),
+        PackageSource::Git(_) => Some((&states.git, "git repository")),
+        PackageSource::Path => Some((&states.path, "local path")),
+        PackageSource::Placeholder(_) => Some((&states.placeholder, "pruned dependencies")),
+    }
+}
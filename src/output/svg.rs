@@ -0,0 +1,453 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    io::{self, Write},
+};
+
+use crate::{
+    cli::Config,
+    dep_info::Dependency,
+    graph::DepGraph,
+    layout::{Layout, LayoutItem},
+};
+
+use super::{
+    dot::{edge_svg_attrs, node_svg_attrs},
+    edge_label, escape_xml,
+    style::{ArrowHead, LineStyle, Shape, background_color, edge_style, node_style},
+    theme::Theme,
+};
+
+const FONT_SIZE: f64 = 14.0;
+/// Estimated average width of a character, since the font the SVG is going to be displayed with
+/// isn't known when laying out the graph.
+const CHAR_WIDTH: f64 = 7.5;
+const LINE_HEIGHT: f64 = 16.0;
+const NODE_HEIGHT: f64 = 36.0;
+/// Space between a node's label and its border.
+const NODE_PADDING: f64 = 10.0;
+/// Space between two items of the same layer.
+const ITEM_GAP: f64 = 24.0;
+/// Space between two layers.
+const LAYER_GAP: f64 = 56.0;
+const MARGIN: f64 = 8.0;
+const ARROW_LENGTH: f64 = 10.0;
+/// Space between the two borders of nodes with a double border.
+const DOUBLE_BORDER_GAP: f64 = 4.0;
+
+/// Lays out the graph in layers like Graphviz' `dot` and writes it as SVG, styled according to
+/// the same theme attributes as the DOT output.
+pub(crate) fn write_svg(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    config: &Config,
+    theme: &Theme,
+) -> io::Result<()> {
+    let layout = Layout::new(graph);
+    let rankdir = config.rankdir.as_deref().unwrap_or("TB");
+    // Whether layers are columns rather than rows
+    let is_horizontal = matches!(rankdir, "LR" | "RL");
+    let is_flipped = matches!(rankdir, "BT" | "RL");
+
+    let mut nodes = HashMap::new();
+    for &item in layout.layers.iter().flatten() {
+        let LayoutItem::Node(idx) = item else { continue };
+        let pkg = &graph[idx];
+
+        let style = node_style(pkg, config, theme);
+        let label = format!("{pkg:?}");
+        let mut width = text_width(&label) + 2.0 * NODE_PADDING;
+        if style.shape == Shape::Ellipse {
+            width *= 1.3;
+        }
+
+        nodes.insert(item, (style, label, width, NODE_HEIGHT));
+    }
+
+    // Size of an item along its layer, and across (i.e. in the direction of the edges)
+    let size = |item: LayoutItem| match nodes.get(&item) {
+        Some(&(_, _, width, height)) if is_horizontal => (height, width),
+        Some(&(_, _, width, height)) => (width, height),
+        None => (0.0, 0.0),
+    };
+
+    let mut upper_neighbors: HashMap<_, Vec<_>> = HashMap::new();
+    for edge in &layout.edges {
+        for pair in edge.items.windows(2) {
+            upper_neighbors.entry(pair[1]).or_default().push(pair[0]);
+        }
+    }
+
+    // Place every item below the average position of its neighbors in the layer above, without
+    // changing the order of the items in the layer. If that pushes the layer to one side, move it
+    // back so it is centered below the neighbors on average.
+    let mut centers: HashMap<LayoutItem, f64> = HashMap::new();
+    for layer in &layout.layers {
+        let mut end = None;
+        let mut offsets = Vec::new();
+        for &item in layer {
+            let (size, _) = size(item);
+            let desired = upper_neighbors.get(&item).map(|neighbors| {
+                neighbors.iter().map(|n| centers[n]).sum::<f64>() / neighbors.len() as f64
+            });
+
+            let mut start = desired.map_or(end.unwrap_or(0.0), |center| center - size / 2.0);
+            if let Some(end) = end {
+                start = start.max(end);
+            }
+            if let Some(desired) = desired {
+                offsets.push(start + size / 2.0 - desired);
+            }
+
+            centers.insert(item, start + size / 2.0);
+            end = Some(start + size + ITEM_GAP);
+        }
+
+        if !offsets.is_empty() {
+            let shift = offsets.iter().sum::<f64>() / offsets.len() as f64;
+            for item in layer {
+                *centers.get_mut(item).unwrap() -= shift;
+            }
+        }
+    }
+
+    let min_start = centers.iter().map(|(&item, &c)| c - size(item).0 / 2.0).fold(0.0, f64::min);
+    let mut total_along = 0.0_f64;
+    for (&item, center) in &mut centers {
+        *center -= min_start;
+        total_along = total_along.max(*center + size(item).0 / 2.0);
+    }
+
+    let mut layer_centers = Vec::new();
+    let mut total_across = 0.0;
+    for layer in &layout.layers {
+        let thickness = layer.iter().map(|&item| size(item).1).fold(0.0, f64::max);
+        layer_centers.push(total_across + thickness / 2.0);
+        total_across += thickness + LAYER_GAP;
+    }
+    total_across = (total_across - LAYER_GAP).max(0.0);
+
+    let item_layers: HashMap<_, _> = layout
+        .layers
+        .iter()
+        .enumerate()
+        .flat_map(|(i, layer)| layer.iter().map(move |&item| (item, i)))
+        .collect();
+
+    // Converts a position along / across the layers to SVG coordinates
+    let to_point = |along: f64, across: f64| {
+        let across = if is_flipped { total_across - across } else { across };
+        if is_horizontal {
+            (across + MARGIN, along + MARGIN)
+        } else {
+            (along + MARGIN, across + MARGIN)
+        }
+    };
+
+    // The SVG elements are collected first so the size of the image is known when writing the
+    // opening tag, including any edge labels that stick out at the side.
+    let mut body = String::new();
+    let (mut width, mut height) = match is_horizontal {
+        true => (total_across + 2.0 * MARGIN, total_along + 2.0 * MARGIN),
+        false => (total_along + 2.0 * MARGIN, total_across + 2.0 * MARGIN),
+    };
+
+    if let Some(bgcolor) = background_color(theme) {
+        let bgcolor = escape_xml(&bgcolor);
+        writeln!(body, "<rect width=\"100%\" height=\"100%\" fill=\"{bgcolor}\"/>").unwrap();
+    }
+
+    for edge in &layout.edges {
+        let style = edge_style(&edge.info, config, theme);
+        if style.line == LineStyle::Invisible {
+            continue;
+        }
+
+        // Positions along / across the layers, from top to bottom
+        let mut points: Vec<_> = edge
+            .items
+            .iter()
+            .map(|item| (centers[item], layer_centers[item_layers[item]]))
+            .collect();
+        let last = points.len() - 1;
+        points[0].1 += size(edge.items[0]).1 / 2.0;
+        points[last].1 -= size(edge.items[last]).1 / 2.0;
+        if edge.is_reversed {
+            points.reverse();
+        }
+
+        let (end_along, end_across) = points[last];
+        let direction = if points[last - 1].1 < end_across { 1.0 } else { -1.0 };
+        if style.arrow_head != ArrowHead::None {
+            points[last].1 -= direction * ARROW_LENGTH;
+        }
+
+        let mut path = String::new();
+        let (x, y) = to_point(points[0].0, points[0].1);
+        write!(path, "M{x:.1},{y:.1}").unwrap();
+        for pair in points.windows(2) {
+            let middle = (pair[0].1 + pair[1].1) / 2.0;
+            let (x1, y1) = to_point(pair[0].0, middle);
+            let (x2, y2) = to_point(pair[1].0, middle);
+            let (x, y) = to_point(pair[1].0, pair[1].1);
+            write!(path, " C{x1:.1},{y1:.1} {x2:.1},{y2:.1} {x:.1},{y:.1}").unwrap();
+        }
+
+        let deps: Vec<&Dependency> =
+            graph.edges_connecting(edge.source, edge.target).map(|e| e.weight()).collect();
+        let (source, target) = (&graph[edge.source], &graph[edge.target]);
+        let mut dep = deps[0].clone();
+        dep.info = edge.info;
+        if deps.iter().any(|d| d.target != dep.target) {
+            dep.target = None;
+        }
+
+        let svg_attrs = edge_svg_attrs(source, target, &dep);
+        let class = match (config.svg_attrs, svg_attrs.get("class")) {
+            (true, Some(class)) => format!("edge {class}"),
+            _ => "edge".to_owned(),
+        };
+        let title = match (config.svg_attrs, svg_attrs.get("tooltip")) {
+            (true, Some(tooltip)) => tooltip.to_owned(),
+            _ => format!("{} -> {}", source.name, target.name),
+        };
+
+        writeln!(body, "<g class=\"{class}\">").unwrap();
+        writeln!(body, "<title>{}</title>", escape_xml(&title)).unwrap();
+        let path_stroke = stroke(&style.color, style.pen_width, style.line);
+        writeln!(body, "<path d=\"{path}\" fill=\"none\"{path_stroke}/>").unwrap();
+
+        if style.arrow_head != ArrowHead::None {
+            let wing = ARROW_LENGTH / 2.5;
+            let base = end_across - direction * ARROW_LENGTH;
+            let corners =
+                [(end_along, end_across), (end_along - wing, base), (end_along + wing, base)];
+            let corners: Vec<_> = corners
+                .into_iter()
+                .map(|(along, across)| {
+                    let (x, y) = to_point(along, across);
+                    format!("{x:.1},{y:.1}")
+                })
+                .collect();
+            let fill = match style.arrow_head {
+                ArrowHead::Empty => "none".to_owned(),
+                _ => escape_xml(&style.color),
+            };
+            writeln!(
+                body,
+                "<polygon points=\"{}\" fill=\"{fill}\"{}/>",
+                corners.join(" "),
+                stroke(&style.color, style.pen_width, LineStyle::Solid),
+            )
+            .unwrap();
+        }
+
+        let mut label: Vec<String> = Vec::new();
+        for dep in &deps {
+            for line in edge_label(dep, target, config) {
+                if !label.contains(&line) {
+                    label.push(line);
+                }
+            }
+        }
+        if !label.is_empty() {
+            // Next to the middle segment of the edge
+            let i = (points.len() - 1) / 2;
+            let along = (points[i].0 + points[i + 1].0) / 2.0 + 6.0;
+            let across = (points[i].1 + points[i + 1].1) / 2.0;
+            let (x, y) = to_point(along, across);
+            let label_width = label.iter().map(|line| text_width(line)).fold(0.0, f64::max);
+            let label_height = label.len() as f64 * LINE_HEIGHT;
+            width = width.max(x + label_width + MARGIN);
+            height = height.max(y + label_height + MARGIN);
+
+            write!(
+                body,
+                "<text x=\"{x:.1}\" y=\"{:.1}\" font-size=\"{}\" fill=\"{}\">",
+                y - label_height / 2.0 + LINE_HEIGHT * 0.75,
+                FONT_SIZE * 0.85,
+                escape_xml(&style.font_color),
+            )
+            .unwrap();
+            for (i, line) in label.iter().enumerate() {
+                let dy = if i == 0 { 0.0 } else { LINE_HEIGHT };
+                write!(body, "<tspan x=\"{x:.1}\" dy=\"{dy}\">{}</tspan>", escape_xml(line))
+                    .unwrap();
+            }
+            writeln!(body, "</text>").unwrap();
+        }
+
+        writeln!(body, "</g>").unwrap();
+    }
+
+    for &item in layout.layers.iter().flatten() {
+        let (LayoutItem::Node(idx), Some((style, label, node_width, node_height))) =
+            (item, nodes.get(&item))
+        else {
+            continue;
+        };
+        let pkg = &graph[idx];
+        if style.line == LineStyle::Invisible {
+            continue;
+        }
+
+        let (cx, cy) = to_point(centers[&item], layer_centers[item_layers[&item]]);
+        let svg_attrs = node_svg_attrs(pkg, config);
+        let class = match (config.svg_attrs, svg_attrs.get("class")) {
+            (true, Some(class)) => format!("node {class}"),
+            _ => "node".to_owned(),
+        };
+        let title = match (config.svg_attrs, svg_attrs.get("tooltip")) {
+            (true, Some(tooltip)) => tooltip.to_owned(),
+            _ => label.clone(),
+        };
+        let url = svg_attrs.get("URL").filter(|_| config.svg_attrs);
+
+        writeln!(body, "<g class=\"{class}\">").unwrap();
+        writeln!(body, "<title>{}</title>", escape_xml(&title)).unwrap();
+        if let Some(url) = url {
+            writeln!(body, "<a href=\"{}\">", escape_xml(url)).unwrap();
+        }
+
+        let stroke = stroke(&style.color, style.pen_width, style.line);
+        let fill = escape_xml(style.fill_color.as_deref().unwrap_or("none"));
+        let element = shape(style.shape, cx, cy, *node_width, *node_height);
+        writeln!(body, "<{element} fill=\"{fill}\"{stroke}/>").unwrap();
+        if style.double_border {
+            let gap = DOUBLE_BORDER_GAP * 2.0;
+            let element = shape(style.shape, cx, cy, node_width + gap, node_height + gap);
+            writeln!(body, "<{element} fill=\"none\"{stroke}/>").unwrap();
+        }
+        writeln!(
+            body,
+            "<text x=\"{cx:.1}\" y=\"{cy:.1}\" text-anchor=\"middle\" dominant-baseline=\"central\" \
+             font-size=\"{FONT_SIZE}\" fill=\"{}\">{}</text>",
+            escape_xml(&style.font_color),
+            escape_xml(label),
+        )
+        .unwrap();
+
+        if url.is_some() {
+            writeln!(body, "</a>").unwrap();
+        }
+        writeln!(body, "</g>").unwrap();
+    }
+
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width:.0}\" height=\"{height:.0}\" \
+         viewBox=\"0 0 {width:.0} {height:.0}\" font-family=\"{}\">",
+        escape_xml(theme.node.get("fontname").unwrap_or("Times,serif")),
+    )?;
+    out.write_all(body.as_bytes())?;
+    writeln!(out, "</svg>")
+}
+
+/// The stroke attributes of an SVG element.
+fn stroke(color: &str, width: f64, line: LineStyle) -> String {
+    let mut stroke = format!(" stroke=\"{}\" stroke-width=\"{width}\"", escape_xml(color));
+    match line {
+        LineStyle::Dashed => stroke += " stroke-dasharray=\"5,2\"",
+        LineStyle::Dotted => stroke += " stroke-dasharray=\"1,5\"",
+        LineStyle::Solid | LineStyle::Invisible => {}
+    }
+
+    stroke
+}
+
+/// The SVG element (without the closing `/>`) for a node shape.
+fn shape(shape: Shape, cx: f64, cy: f64, width: f64, height: f64) -> String {
+    let (left, right) = (cx - width / 2.0, cx + width / 2.0);
+    let (top, bottom) = (cy - height / 2.0, cy + height / 2.0);
+    let corner = height / 4.0;
+
+    let polygon = |points: &[(f64, f64)]| {
+        let points: Vec<_> = points.iter().map(|(x, y)| format!("{x:.1},{y:.1}")).collect();
+        format!("polygon points=\"{}\"", points.join(" "))
+    };
+
+    match shape {
+        Shape::Box => format!(
+            "rect x=\"{left:.1}\" y=\"{top:.1}\" width=\"{width:.1}\" height=\"{height:.1}\""
+        ),
+        Shape::Hexagon => polygon(&[
+            (left, cy),
+            (left + corner, top),
+            (right - corner, top),
+            (right, cy),
+            (right - corner, bottom),
+            (left + corner, bottom),
+        ]),
+        Shape::Octagon => polygon(&[
+            (left, top + corner),
+            (left + corner, top),
+            (right - corner, top),
+            (right, top + corner),
+            (right, bottom - corner),
+            (right - corner, bottom),
+            (left + corner, bottom),
+            (left, bottom - corner),
+        ]),
+        Shape::Note => format!(
+            "path d=\"M{left:.1},{top:.1} H{:.1} L{right:.1},{:.1} V{bottom:.1} H{left:.1} Z \
+             M{:.1},{top:.1} V{:.1} H{right:.1}\"",
+            right - corner,
+            top + corner,
+            right - corner,
+            top + corner,
+        ),
+        Shape::Folder => format!(
+            "path d=\"M{left:.1},{top:.1} H{:.1} L{:.1},{:.1} H{right:.1} V{bottom:.1} \
+             H{left:.1} Z\"",
+            left + width / 3.0,
+            left + width / 3.0 + corner,
+            top + corner,
+        ),
+        Shape::Point => format!("circle cx=\"{cx:.1}\" cy=\"{cy:.1}\" r=\"2\""),
+        Shape::Ellipse => format!(
+            "ellipse cx=\"{cx:.1}\" cy=\"{cy:.1}\" rx=\"{:.1}\" ry=\"{:.1}\"",
+            width / 2.0,
+            height / 2.0
+        ),
+    }
+}
+
+fn text_width(text: &str) -> f64 {
+    text.chars().count() as f64 * CHAR_WIDTH
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cli::parse_view_options, dep_info::DepKind, graph::test_graph};
+
+    #[test]
+    fn theme_colors_are_escaped() {
+        let graph = test_graph(&["app"], &[("app", "util", DepKind::NORMAL)]);
+        let config = parse_view_options(&["--edge-labels".to_owned()]).unwrap();
+        let mut theme = Theme::builtin("default").unwrap();
+        let color = r#"red" onload="alert(1)"#;
+        theme.graph.set("bgcolor", color);
+        theme.node.set("color", color);
+        theme.node.set("fontcolor", color);
+        theme.node.set("style", "filled");
+        theme.node.set("fillcolor", color);
+        theme.edge.set("color", color);
+        theme.edge.set("fontcolor", color);
+
+        let mut out = Vec::new();
+        write_svg(&mut out, &graph, &config, &theme).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+
+        assert!(!svg.contains("onload=\""), "{svg}");
+        assert!(svg.contains("fill=\"red&quot; onload=&quot;alert(1)\""), "{svg}");
+        assert!(svg.contains("stroke=\"red&quot; onload=&quot;alert(1)\""), "{svg}");
+    }
+
+    #[test]
+    fn workspace_clusters_are_not_supported() {
+        let args = ["--svg", "graph.svg", "--cluster-workspaces"].map(str::to_owned);
+        let err = parse_view_options(&args).err().unwrap();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+    }
+}
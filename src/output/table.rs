@@ -0,0 +1,208 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    iter,
+};
+
+use petgraph::{Direction, algo::tarjan_scc, stable_graph::NodeIndex};
+
+use crate::graph::{DepGraph, node_depths};
+
+use super::{
+    escape_csv,
+    properties::{EDGE_PROPERTIES, NODE_PROPERTIES, edge_properties, node_properties},
+    sorted_edges,
+};
+
+/// Metrics computed for every package, in addition to its properties.
+const NODE_METRICS: [&str; 4] = ["in_degree", "out_degree", "depth", "transitive_count"];
+
+/// How the fields of a row are separated.
+#[derive(Clone, Copy)]
+pub(super) enum Separator {
+    Comma,
+    Tab,
+}
+
+impl Separator {
+    fn as_char(self) -> char {
+        match self {
+            Self::Comma => ',',
+            Self::Tab => '\t',
+        }
+    }
+
+    fn escape(self, field: &str) -> Cow<'_, str> {
+        match self {
+            Self::Comma => escape_csv(field),
+            // TSV has no quoting, so characters that would break up the table are replaced
+            Self::Tab if field.contains(['\t', '\n', '\r']) => {
+                Cow::Owned(field.replace(['\t', '\n', '\r'], " "))
+            }
+            Self::Tab => Cow::Borrowed(field),
+        }
+    }
+}
+
+/// Writes one row per package with its properties and metrics:
+///
+/// - `in_degree` / `out_degree`: the number of packages that depend on it / that it depends on
+/// - `depth`: the distance from the workspace members (or packages nothing depends on)
+/// - `transitive_count`: the number of packages it depends on, directly or transitively
+pub(super) fn write_nodes(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    separator: Separator,
+) -> io::Result<()> {
+    let header = NODE_PROPERTIES.iter().map(|(name, _)| *name).chain(NODE_METRICS);
+    write_row(out, separator, iter_some(iter::once("id").chain(header)))?;
+
+    let depths = node_depths(graph, false);
+    let transitive_counts = transitive_counts(graph);
+    let mut nodes: Vec<_> = graph.node_indices().collect();
+    nodes.sort_by(|&a, &b| {
+        let (a, b) = (&graph[a], &graph[b]);
+        (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id))
+    });
+
+    for idx in nodes {
+        let pkg = &graph[idx];
+        let degree = |direction| {
+            graph.neighbors_directed(idx, direction).collect::<HashSet<_>>().len().to_string()
+        };
+        let metrics = [
+            Some(degree(Direction::Incoming)),
+            Some(degree(Direction::Outgoing)),
+            depths.get(&idx).map(ToString::to_string),
+            Some(transitive_counts[&idx].to_string()),
+        ];
+
+        let values = node_properties(pkg).into_iter().chain(metrics);
+        write_row(out, separator, iter::once(Some(pkg.id.clone())).chain(values))?;
+    }
+
+    Ok(())
+}
+
+/// Writes one row per dependency edge, with the IDs of the dependent (`from`) and the dependency
+/// (`to`) and the dependency's properties.
+pub(super) fn write_edges(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    separator: Separator,
+) -> io::Result<()> {
+    let header = ["from", "to"].into_iter().chain(EDGE_PROPERTIES.iter().map(|(name, _)| *name));
+    write_row(out, separator, iter_some(header))?;
+
+    for (source, target, dep) in sorted_edges(graph) {
+        let ids = [Some(source.id.clone()), Some(target.id.clone())];
+        write_row(out, separator, ids.into_iter().chain(edge_properties(dep)))?;
+    }
+
+    Ok(())
+}
+
+/// The number of packages every package depends on, directly or transitively. Rather than
+/// searching the graph from every package, the dependencies of each dependency cycle (strongly
+/// connected component) are collected once, from those of the components it depends on.
+fn transitive_counts(graph: &DepGraph) -> HashMap<NodeIndex<u16>, usize> {
+    // The components are in reverse topological order, so dependencies come before dependents
+    let components = tarjan_scc(graph);
+    let component_of: HashMap<_, _> = components
+        .iter()
+        .enumerate()
+        .flat_map(|(i, component)| component.iter().map(move |&idx| (idx, i)))
+        .collect();
+
+    let mut component_deps: Vec<HashSet<usize>> = Vec::with_capacity(components.len());
+    let mut counts = HashMap::new();
+    for (i, component) in components.iter().enumerate() {
+        let mut deps = HashSet::new();
+        for dep in
+            component.iter().flat_map(|&idx| graph.neighbors_directed(idx, Direction::Outgoing))
+        {
+            let dep = component_of[&dep];
+            if dep != i && deps.insert(dep) {
+                deps.extend(&component_deps[dep]);
+            }
+        }
+
+        // The other packages of the same cycle are dependencies as well
+        let count =
+            component.len() - 1 + deps.iter().map(|&dep| components[dep].len()).sum::<usize>();
+        counts.extend(component.iter().map(|&idx| (idx, count)));
+        component_deps.push(deps);
+    }
+
+    counts
+}
+
+fn write_row<S: AsRef<str>>(
+    out: &mut impl Write,
+    separator: Separator,
+    values: impl IntoIterator<Item = Option<S>>,
+) -> io::Result<()> {
+    for (i, value) in values.into_iter().enumerate() {
+        if i > 0 {
+            write!(out, "{}", separator.as_char())?;
+        }
+        if let Some(value) = value {
+            write!(out, "{}", separator.escape(value.as_ref()))?;
+        }
+    }
+    writeln!(out)
+}
+
+fn iter_some<T>(iter: impl IntoIterator<Item = T>) -> impl Iterator<Item = Option<T>> {
+    iter.into_iter().map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dep_info::DepKind,
+        graph::{reachable, test_graph},
+    };
+
+    #[test]
+    fn transitive_counts_of_a_diamond() {
+        // b and c both depend on d, which is only counted once for app
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "b", DepKind::NORMAL),
+                ("app", "c", DepKind::NORMAL),
+                ("b", "d", DepKind::NORMAL),
+                ("c", "d", DepKind::NORMAL),
+                ("d", "e", DepKind::NORMAL),
+            ],
+        );
+        let counts = transitive_counts(&graph);
+
+        let mut names: Vec<_> =
+            graph.node_indices().map(|idx| (graph[idx].name.as_str(), counts[&idx])).collect();
+        names.sort_unstable();
+        assert_eq!(names, [("app", 4), ("b", 2), ("c", 2), ("d", 1), ("e", 0)]);
+        for idx in graph.node_indices() {
+            assert_eq!(counts[&idx], reachable(&graph, [idx], None).len() - 1);
+        }
+    }
+
+    #[test]
+    fn transitive_counts_in_cycles() {
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "a", DepKind::NORMAL),
+                ("a", "app", DepKind::DEV),
+                ("a", "b", DepKind::NORMAL),
+            ],
+        );
+        let counts = transitive_counts(&graph);
+        for idx in graph.node_indices() {
+            assert_eq!(counts[&idx], reachable(&graph, [idx], None).len() - 1);
+        }
+    }
+}
@@ -0,0 +1,601 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+};
+
+use crate::{
+    dep_info::DepKind,
+    graph::DepGraph,
+    layout::{Layout, LayoutEdge, LayoutItem},
+    package::Package,
+};
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+/// Horizontal space between two items of the same layer.
+const ITEM_GAP: usize = 2;
+
+/// Options for `write_terminal`.
+#[derive(Debug)]
+pub(crate) struct TerminalOptions {
+    /// use plain ASCII characters instead of Unicode box-drawing characters
+    pub ascii: bool,
+    /// use ANSI escape codes to color nodes and edges
+    pub color: bool,
+    /// maximum width of the output in characters; wider layers are truncated
+    pub max_width: usize,
+}
+
+/// Writes the graph as a layered diagram made of text, with packages drawn as boxes from top
+/// (workspace members) to bottom.
+pub(crate) fn write_terminal(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    options: &TerminalOptions,
+) -> io::Result<()> {
+    let layout = Layout::new(graph);
+
+    let label = |item: LayoutItem| match item {
+        LayoutItem::Node(idx) => format!("{:?}", graph[idx]),
+        LayoutItem::Dummy { .. } => String::new(),
+    };
+    let item_width = |item: LayoutItem| match item {
+        LayoutItem::Node(_) => label(item).chars().count() + 4,
+        LayoutItem::Dummy { .. } => 1,
+    };
+
+    // Horizontal position of every visible item, and the number of packages that were left out
+    // of each layer because it would be too wide otherwise
+    let mut positions = HashMap::new();
+    let mut truncated = Vec::new();
+    let mut layer_widths = Vec::new();
+    for layer in &layout.layers {
+        let mut x = 0;
+        let mut num_truncated = 0;
+        for &item in layer {
+            let width = item_width(item);
+            // Leave some space for the truncation marker
+            if num_truncated > 0
+                || x + width + truncation_marker(999, options.ascii).chars().count()
+                    > options.max_width
+            {
+                if matches!(item, LayoutItem::Node(_)) {
+                    num_truncated += 1;
+                }
+                continue;
+            }
+
+            positions.insert(item, x);
+            x += width + ITEM_GAP;
+        }
+
+        truncated.push(num_truncated);
+        layer_widths.push(x.saturating_sub(ITEM_GAP));
+    }
+
+    // Center all layers
+    let width = layer_widths.iter().copied().max().unwrap_or(0);
+    for (layer, &layer_width) in layout.layers.iter().zip(&layer_widths) {
+        let offset = (width - layer_width) / 2;
+        for item in layer {
+            if let Some(x) = positions.get_mut(item) {
+                *x += offset;
+            }
+        }
+    }
+
+    // Connection point of an item for edges, i.e. the center of a package's box
+    let port = |item: LayoutItem| positions.get(&item).map(|&x| x + item_width(item) / 2);
+    // Edges are only drawn if all the items they pass through are visible
+    let is_visible = |edge: &LayoutEdge| edge.items.iter().all(|item| positions.contains_key(item));
+
+    // Items with edges leaving them downwards, which get an exit point at the bottom of their box
+    let has_edges_below: HashSet<_> = layout
+        .edges
+        .iter()
+        .filter(|edge| is_visible(edge))
+        .flat_map(|edge| edge.items[..edge.items.len() - 1].iter().copied())
+        .collect();
+
+    let mut canvas = Canvas::default();
+    let mut y = 0;
+    for (i, layer) in layout.layers.iter().enumerate() {
+        for &item in layer {
+            let Some(&x) = positions.get(&item) else { continue };
+            match item {
+                LayoutItem::Node(idx) => {
+                    let exit = has_edges_below.contains(&item).then(|| port(item).unwrap());
+                    canvas.draw_box(x, y, &label(item), &graph[idx], exit, options);
+                }
+                LayoutItem::Dummy { edge, .. } => {
+                    if !is_visible(&layout.edges[edge]) {
+                        continue;
+                    }
+                    let color = ansi_color(layout.edges[edge].info.kind);
+                    canvas.draw_vertical(x, y, y + 2, Some(color));
+                }
+            }
+        }
+
+        if truncated[i] > 0 {
+            let x = positions
+                .iter()
+                .filter(|(item, _)| layer.contains(item))
+                .map(|(&item, &x)| x + item_width(item) + ITEM_GAP)
+                .max()
+                .unwrap_or(0);
+            canvas.draw_text(x, y + 1, &truncation_marker(truncated[i], options.ascii), None);
+        }
+
+        y += 3;
+
+        // Draw the edges between this layer and the next one. All edges starting at the same item
+        // share one horizontal line (a bus), and every bus gets a row (track) of its own, so lines
+        // only join where an edge branches. Everywhere else, lines cross.
+        let Some(next_layer) = layout.layers.get(i + 1) else { break };
+        let mut buses: Vec<Bus> = Vec::new();
+        for edge in &layout.edges {
+            let Some(pos) = edge.items.iter().position(|item| layer.contains(item)) else {
+                continue;
+            };
+            let Some(&lower) = edge.items.get(pos + 1) else { continue };
+            debug_assert!(next_layer.contains(&lower));
+
+            if !is_visible(edge) {
+                continue;
+            }
+
+            let from = port(edge.items[pos]).unwrap();
+            let to = port(lower).unwrap();
+            let arrow = if edge.is_reversed {
+                (pos == 0).then_some(false)
+            } else {
+                (pos + 2 == edge.items.len()).then_some(true)
+            };
+
+            let segment = Segment { to, color: ansi_color(edge.info.kind), arrow };
+            match buses.iter_mut().find(|bus| bus.from == from) {
+                Some(bus) => bus.targets.push(segment),
+                None => buses.push(Bus { from, targets: vec![segment] }),
+            }
+        }
+
+        buses.sort_by_key(|bus| bus.left());
+        let order = track_order(&buses);
+        let num_tracks = order.iter().filter(|&&i| !buses[i].is_straight()).count();
+        let bottom = y + num_tracks + 1;
+
+        let mut track_ys = vec![None; buses.len()];
+        for (track, i) in order.into_iter().filter(|&i| !buses[i].is_straight()).enumerate() {
+            let track_y = y + 1 + track;
+            let bus = &buses[i];
+            canvas.draw_horizontal(bus.left(), bus.right(), track_y, bus.color());
+            canvas.join(bus.from, track_y);
+            for segment in &bus.targets {
+                canvas.join(segment.to, track_y);
+            }
+            track_ys[i] = Some(track_y);
+        }
+
+        // The vertical lines are drawn after all horizontal ones, so they keep their color where
+        // they cross
+        for (bus, track_y) in buses.iter().zip(track_ys) {
+            let bus_color = bus.color();
+            match track_y {
+                Some(track_y) => canvas.draw_vertical(bus.from, y, track_y, bus_color),
+                // Lines going straight down connect directly
+                None => canvas.draw_vertical(bus.from, y, bottom, bus_color),
+            }
+            if bus.targets.iter().any(|segment| segment.arrow == Some(false)) {
+                canvas.draw_arrow(bus.from, y, false, options, bus_color);
+            }
+
+            for &Segment { to, color, arrow } in &bus.targets {
+                if let Some(track_y) = track_y {
+                    canvas.draw_vertical(to, track_y, bottom, Some(color));
+                }
+                if arrow == Some(true) {
+                    canvas.draw_arrow(to, bottom, true, options, Some(color));
+                }
+            }
+        }
+
+        y = bottom + 1;
+    }
+
+    canvas.write(out, options)
+}
+
+/// The edges leaving one item downwards, sharing a horizontal line.
+struct Bus {
+    /// horizontal position of the upper end
+    from: usize,
+    targets: Vec<Segment>,
+}
+
+impl Bus {
+    fn left(&self) -> usize {
+        self.targets.iter().map(|segment| segment.to).fold(self.from, usize::min)
+    }
+
+    fn right(&self) -> usize {
+        self.targets.iter().map(|segment| segment.to).fold(self.from, usize::max)
+    }
+
+    /// Whether this is a single edge going straight down, which doesn't need a horizontal line.
+    fn is_straight(&self) -> bool {
+        self.left() == self.right()
+    }
+
+    /// The color of the shared part, if all edges have the same one.
+    fn color(&self) -> Option<&'static str> {
+        let color = self.targets[0].color;
+        self.targets.iter().all(|segment| segment.color == color).then_some(color)
+    }
+}
+
+/// The order in which the buses get their tracks, from top to bottom.
+///
+/// The vertical line from the upper end of a bus to its track must not overlap with a vertical line
+/// going down from the track of another bus at the same position, so a bus starting where another
+/// one ends gets a track above it. Otherwise, and if this isn't possible because of a cycle, the
+/// buses keep their order.
+fn track_order(buses: &[Bus]) -> Vec<usize> {
+    let must_wait = |i: usize, placed: &[bool]| {
+        buses.iter().enumerate().any(|(j, other)| {
+            !placed[j] && j != i && buses[i].targets.iter().any(|s| s.to == other.from)
+        })
+    };
+
+    let mut placed = vec![false; buses.len()];
+    let mut order = Vec::with_capacity(buses.len());
+    while order.len() < buses.len() {
+        let next = (0..buses.len())
+            .find(|&i| !placed[i] && !must_wait(i, &placed))
+            .or_else(|| (0..buses.len()).find(|&i| !placed[i]))
+            .unwrap();
+        placed[next] = true;
+        order.push(next);
+    }
+
+    order
+}
+
+/// The part of an edge between two adjacent layers.
+#[derive(Clone, Copy)]
+struct Segment {
+    /// horizontal position of the lower end
+    to: usize,
+    color: &'static str,
+    /// arrow at the lower end (`Some(true)`), the upper end (`Some(false)`) or none
+    arrow: Option<bool>,
+}
+
+fn truncation_marker(num_truncated: usize, ascii: bool) -> String {
+    let ellipsis = if ascii { "..." } else { "…" };
+    format!("{ellipsis} +{num_truncated} more")
+}
+
+/// Terminal colors matching the colors of the default theme.
+fn ansi_color(kind: DepKind) -> &'static str {
+    match kind {
+        DepKind::NORMAL => "",
+        DepKind::DEV => "34",
+        DepKind::BUILD => "92",
+        DepKind::BUILD_OF_DEV => "96",
+        DepKind::NORMAL_AND_BUILD => "32",
+        DepKind::DEV_AND_BUILD => "35",
+        DepKind::NORMAL_AND_BUILD_OF_DEV => "36",
+        DepKind::DEV_AND_BUILD_OF_DEV => "94",
+        DepKind::UNKNOWN => "31",
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Cell {
+    /// directions of lines leaving this cell, used if `text` is `None`
+    lines: u8,
+    /// whether the horizontal and vertical lines in this cell are connected, rather than crossing
+    joined: bool,
+    text: Option<char>,
+    /// ANSI SGR parameters like "34" for blue text, or "" for the default color
+    color: &'static str,
+}
+
+#[derive(Default)]
+struct Canvas {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Canvas {
+    fn cell(&mut self, x: usize, y: usize) -> &mut Cell {
+        if self.rows.len() <= y {
+            self.rows.resize_with(y + 1, Vec::new);
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, Cell::default());
+        }
+
+        &mut row[x]
+    }
+
+    fn draw_text(&mut self, x: usize, y: usize, text: &str, color: Option<&'static str>) {
+        for (i, c) in text.chars().enumerate() {
+            let cell = self.cell(x + i, y);
+            cell.text = Some(c);
+            cell.color = color.unwrap_or("");
+        }
+    }
+
+    fn draw_line(&mut self, x: usize, y: usize, lines: u8, color: Option<&'static str>) {
+        let cell = self.cell(x, y);
+        cell.lines |= lines;
+        if let Some(color) = color {
+            cell.color = color;
+        }
+    }
+
+    fn join(&mut self, x: usize, y: usize) {
+        self.cell(x, y).joined = true;
+    }
+
+    fn draw_vertical(&mut self, x: usize, top: usize, bottom: usize, color: Option<&'static str>) {
+        for y in top..=bottom {
+            // A line starting at a horizontal line doesn't continue upwards, while one starting
+            // below a box or an arrow does
+            let lines = if y == top && self.cell(x, y).lines != 0 {
+                DOWN
+            } else if y == bottom {
+                UP
+            } else {
+                UP | DOWN
+            };
+            self.draw_line(x, y, lines, color);
+        }
+    }
+
+    fn draw_horizontal(
+        &mut self,
+        left: usize,
+        right: usize,
+        y: usize,
+        color: Option<&'static str>,
+    ) {
+        for x in left..=right {
+            let mut lines = LEFT | RIGHT;
+            if x == left {
+                lines = RIGHT;
+            } else if x == right {
+                lines = LEFT;
+            }
+            self.draw_line(x, y, lines, color);
+        }
+    }
+
+    fn draw_arrow(
+        &mut self,
+        x: usize,
+        y: usize,
+        down: bool,
+        options: &TerminalOptions,
+        color: Option<&'static str>,
+    ) {
+        let arrow = match (down, options.ascii) {
+            (true, false) => '▼',
+            (false, false) => '▲',
+            (true, true) => 'v',
+            (false, true) => '^',
+        };
+        self.draw_text(x, y, &arrow.to_string(), color);
+    }
+
+    fn draw_box(
+        &mut self,
+        x: usize,
+        y: usize,
+        label: &str,
+        pkg: &Package,
+        exit: Option<usize>,
+        options: &TerminalOptions,
+    ) {
+        // corners, horizontal, vertical, exit point at the bottom
+        let (tl, tr, bl, br, h, v, exit_char) = match (options.ascii, pkg.is_ws_member) {
+            (false, true) => ('╔', '╗', '╚', '╝', '═', '║', '╤'),
+            (false, false) if pkg.dep_info.is_optional => ('┌', '┐', '└', '┘', '┄', '┆', '┬'),
+            (false, false) => ('┌', '┐', '└', '┘', '─', '│', '┬'),
+            (true, true) => ('#', '#', '#', '#', '=', '#', '#'),
+            (true, false) if pkg.dep_info.is_optional => ('+', '+', '+', '+', '.', ':', '+'),
+            (true, false) => ('+', '+', '+', '+', '-', '|', '+'),
+        };
+
+        let inner_width = label.chars().count() + 2;
+        let horizontal: String = std::iter::repeat_n(h, inner_width).collect();
+        let color = Some(ansi_color(pkg.dep_info.kind));
+
+        self.draw_text(x, y, &format!("{tl}{horizontal}{tr}"), color);
+        self.draw_text(x, y + 1, &v.to_string(), color);
+        self.draw_text(x + 1, y + 1, &format!(" {label} "), None);
+        self.draw_text(x + inner_width + 1, y + 1, &v.to_string(), color);
+        self.draw_text(x, y + 2, &format!("{bl}{horizontal}{br}"), color);
+        if let Some(exit) = exit {
+            self.draw_text(exit, y + 2, &exit_char.to_string(), color);
+        }
+
+        // Target-specific packages get a grey background, like in the DOT output
+        if pkg.dep_info.is_target_dep && options.color {
+            for i in 0..inner_width {
+                self.cell(x + 1 + i, y + 1).color = "100";
+            }
+        }
+    }
+
+    fn write(&self, out: &mut impl Write, options: &TerminalOptions) -> io::Result<()> {
+        for row in &self.rows {
+            // Leave out trailing blanks, before adding the escape codes
+            let len = row.iter().rposition(|cell| cell.text.is_some() || cell.lines != 0);
+            let row = &row[..len.map_or(0, |i| i + 1)];
+
+            let mut current_color = "";
+            let mut line = String::new();
+            for cell in row {
+                if options.color && cell.color != current_color {
+                    line += "\x1b[0m";
+                    if !cell.color.is_empty() {
+                        line += &format!("\x1b[{}m", cell.color);
+                    }
+                    current_color = cell.color;
+                }
+
+                line.push(match cell.text {
+                    Some(c) => c,
+                    // Where lines cross, the vertical one is drawn through the horizontal one
+                    None if !cell.joined
+                        && cell.lines & (UP | DOWN) != 0
+                        && cell.lines & (LEFT | RIGHT) != 0 =>
+                    {
+                        line_char(UP | DOWN, options.ascii)
+                    }
+                    None => line_char(cell.lines, options.ascii),
+                });
+            }
+            if options.color && !current_color.is_empty() {
+                line += "\x1b[0m";
+            }
+
+            writeln!(out, "{line}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn line_char(lines: u8, ascii: bool) -> char {
+    if ascii {
+        return match lines {
+            0 => ' ',
+            _ if lines & (LEFT | RIGHT) == 0 => '|',
+            _ if lines & (UP | DOWN) == 0 => '-',
+            _ => '+',
+        };
+    }
+
+    match lines {
+        0 => ' ',
+        x if x == UP | DOWN | LEFT | RIGHT => '┼',
+        x if x == DOWN | LEFT | RIGHT => '┬',
+        x if x == UP | LEFT | RIGHT => '┴',
+        x if x == UP | DOWN | RIGHT => '├',
+        x if x == UP | DOWN | LEFT => '┤',
+        x if x == DOWN | RIGHT => '┌',
+        x if x == DOWN | LEFT => '┐',
+        x if x == UP | RIGHT => '└',
+        x if x == UP | LEFT => '┘',
+        _ if lines & (LEFT | RIGHT) == 0 => '│',
+        _ => '─',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::test_graph;
+
+    fn render(ascii: bool, color: bool) -> Vec<String> {
+        let graph = test_graph(
+            &["app"],
+            &[
+                ("app", "left", DepKind::NORMAL),
+                ("app", "right", DepKind::DEV),
+                ("left", "base", DepKind::NORMAL),
+                ("right", "base", DepKind::NORMAL),
+            ],
+        );
+        let options = TerminalOptions { ascii, color, max_width: 100 };
+        let mut out = Vec::new();
+        write_terminal(&mut out, &graph, &options).unwrap();
+        String::from_utf8(out).unwrap().lines().map(ToOwned::to_owned).collect()
+    }
+
+    #[test]
+    fn diamond_ascii() {
+        let expected = [
+            "      #=====#",
+            "      # app #",
+            "      #==#==#",
+            "         |",
+            "    +----+----+",
+            "    v         v",
+            "+------+  +-------+",
+            "| left |  | right |",
+            "+---+--+  +---+---+",
+            "    |         |",
+            "    +----+    |",
+            "         +----+",
+            "         v",
+            "     +------+",
+            "     | base |",
+            "     +------+",
+        ];
+        assert_eq!(render(true, false), expected);
+    }
+
+    #[test]
+    fn diamond_unicode() {
+        let expected = [
+            "      ╔═════╗",
+            "      ║ app ║",
+            "      ╚══╤══╝",
+            "         │",
+            "    ┌────┴────┐",
+            "    ▼         ▼",
+            "┌──────┐  ┌───────┐",
+            "│ left │  │ right │",
+            "└───┬──┘  └───┬───┘",
+            "    │         │",
+            "    └────┐    │",
+            "         ├────┘",
+            "         ▼",
+            "     ┌──────┐",
+            "     │ base │",
+            "     └──────┘",
+        ];
+        assert_eq!(render(false, false), expected);
+    }
+
+    #[test]
+    fn colors_are_reset_after_trailing_blanks_are_removed() {
+        for line in render(false, true) {
+            assert!(line.ends_with("\x1b[0m") || !line.contains('\x1b'), "{line:?}");
+            assert!(!line.trim_end_matches("\x1b[0m").ends_with(' '), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn crossing_lines_are_not_joined() {
+        let mut canvas = Canvas::default();
+        // A bus from 2 to 0 and 4 on row 1, crossed by a vertical line at 3
+        canvas.draw_horizontal(0, 4, 1, None);
+        for x in [0, 2, 4] {
+            canvas.join(x, 1);
+        }
+        canvas.draw_vertical(2, 0, 1, None);
+        canvas.draw_vertical(0, 1, 2, None);
+        canvas.draw_vertical(4, 1, 2, None);
+        canvas.draw_vertical(3, 0, 2, None);
+
+        for (ascii, expected) in
+            [(true, ["  ||", "+-+|+", "|  ||"]), (false, ["  ││", "┌─┴│┐", "│  ││"])]
+        {
+            let options = TerminalOptions { ascii, color: false, max_width: 100 };
+            let mut out = Vec::new();
+            canvas.write(&mut out, &options).unwrap();
+            let lines: Vec<_> =
+                String::from_utf8(out).unwrap().lines().map(str::to_owned).collect();
+            assert_eq!(lines, expected);
+        }
+    }
+}
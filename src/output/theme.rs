@@ -0,0 +1,216 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+};
+
+use anyhow::{Context, bail};
+use serde::Deserialize;
+
+use crate::dep_info::DepKind;
+
+/// Names of the themes that are built into cargo-depgraph.
+pub(crate) const BUILTIN_THEMES: [&str; 4] = ["default", "colorblind", "dark", "monochrome"];
+
+/// Maps dependency kinds and the other properties of packages and dependencies to DOT attributes.
+///
+/// The built-in themes in the `themes` directory use the same format as user theme files.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Theme {
+    /// attributes of the graph itself
+    pub graph: Attrs,
+    /// default attributes for all nodes
+    pub node: Attrs,
+    /// default attributes for all edges
+    pub edge: Attrs,
+    /// attributes for nodes and edges of a given dependency kind
+    pub kind: KindAttrs,
+    pub node_state: NodeStateAttrs,
+    pub edge_state: EdgeStateAttrs,
+}
+
+impl Theme {
+    pub fn builtin(name: &str) -> anyhow::Result<Self> {
+        let theme = match name {
+            "default" => include_str!("themes/default.toml"),
+            "colorblind" => include_str!("themes/colorblind.toml"),
+            "dark" => include_str!("themes/dark.toml"),
+            "monochrome" => include_str!("themes/monochrome.toml"),
+            _ => bail!("unknown theme `{name}`"),
+        };
+
+        toml::from_str(theme).with_context(|| format!("failed to parse built-in theme `{name}`"))
+    }
+
+    pub fn from_file(path: &str) -> anyhow::Result<Self> {
+        let theme = fs::read_to_string(path)
+            .with_context(|| format!("failed to read theme file `{path}`"))?;
+        toml::from_str(&theme).with_context(|| format!("failed to parse theme file `{path}`"))
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct KindAttrs {
+    pub normal: Attrs,
+    pub dev: Attrs,
+    pub build: Attrs,
+    pub build_of_dev: Attrs,
+    pub normal_and_build: Attrs,
+    pub dev_and_build: Attrs,
+    pub normal_and_build_of_dev: Attrs,
+    pub dev_and_build_of_dev: Attrs,
+    pub unknown: Attrs,
+}
+
+impl KindAttrs {
+    pub fn get(&self, kind: DepKind) -> &Attrs {
+        match kind {
+            DepKind::NORMAL => &self.normal,
+            DepKind::DEV => &self.dev,
+            DepKind::BUILD => &self.build,
+            DepKind::BUILD_OF_DEV => &self.build_of_dev,
+            DepKind::NORMAL_AND_BUILD => &self.normal_and_build,
+            DepKind::DEV_AND_BUILD => &self.dev_and_build,
+            DepKind::NORMAL_AND_BUILD_OF_DEV => &self.normal_and_build_of_dev,
+            DepKind::DEV_AND_BUILD_OF_DEV => &self.dev_and_build_of_dev,
+            DepKind::UNKNOWN => &self.unknown,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct NodeStateAttrs {
+    pub workspace_member: Attrs,
+    /// placeholder for dependencies removed by --prune
+    pub placeholder: Attrs,
+    pub alt_registry: Attrs,
+    pub git: Attrs,
+    /// path dependency outside of the workspace
+    pub path: Attrs,
+    pub proc_macro: Attrs,
+    pub target: Attrs,
+    pub optional: Attrs,
+    /// part of a path highlighted with --highlight-paths
+    pub highlighted: Attrs,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct EdgeStateAttrs {
+    pub target: Attrs,
+    pub optional: Attrs,
+    pub transitively_optional: Attrs,
+    /// part of a path highlighted with --highlight-paths
+    pub highlighted: Attrs,
+}
+
+/// An ordered list of DOT attributes.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(try_from = "toml::Table")]
+pub(crate) struct Attrs(Vec<(String, String)>);
+
+impl Attrs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Sets an attribute, overwriting any previous value for the same key.
+    ///
+    /// `style` is special-cased since Graphviz supports combining multiple styles like
+    /// `"dashed,filled"`, so that a theme can independently style e.g. optional and
+    /// target-specific dependencies.
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        let value = value.into();
+        match self.0.iter_mut().find(|(k, _)| k == key) {
+            Some((_, old)) if key == "style" => {
+                old.push(',');
+                old.push_str(&value);
+            }
+            Some((_, old)) => *old = value,
+            None => self.0.push((key.to_owned(), value)),
+        }
+    }
+
+    pub fn extend(&mut self, other: &Self) {
+        for (key, value) in &other.0 {
+            self.set(key, value.as_str());
+        }
+    }
+}
+
+impl TryFrom<toml::Table> for Attrs {
+    type Error = String;
+
+    fn try_from(table: toml::Table) -> Result<Self, Self::Error> {
+        let attrs = table
+            .into_iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    toml::Value::String(s) => s,
+                    toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                        value.to_string()
+                    }
+                    _ => return Err(format!("unsupported value for attribute `{key}`: {value}")),
+                };
+
+                Ok((key, value))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self(attrs))
+    }
+}
+
+impl Display for Attrs {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, (key, value)) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{key} = {}", quote(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Quotes an ID or attribute value for DOT. Line breaks are written as `\n`, which centers the
+/// lines of labels.
+pub(super) fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_themes_mark_target_specific_edges() {
+        for name in BUILTIN_THEMES {
+            let theme = Theme::builtin(name).unwrap();
+            assert_eq!(theme.edge_state.target.get("arrowhead"), Some("empty"), "theme `{name}`");
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::{
+    cmp::Ordering,
+    collections::HashSet,
+    io::{self, Write},
+};
+
+use petgraph::{Direction, stable_graph::NodeIndex, visit::EdgeRef};
+
+use super::describe;
+use crate::{
+    dep_info::{DepInfo, DepKind},
+    graph::DepGraph,
+    package::{Package, PackageSource},
+};
+
+/// Writes the graph as an indented tree for every workspace member, similar to `cargo tree`.
+///
+/// Packages whose dependencies were already listed further up are marked with `(*)` and their
+/// dependencies are not listed again.
+pub(crate) fn write_tree(out: &mut impl Write, graph: &DepGraph) -> io::Result<()> {
+    let mut roots: Vec<_> = graph
+        .node_indices()
+        .filter(|&idx| {
+            graph[idx].is_ws_member
+                || graph.neighbors_directed(idx, Direction::Incoming).next().is_none()
+        })
+        .collect();
+    roots.sort_by(|&a, &b| cmp_packages(&graph[a], &graph[b]));
+
+    let mut printed = HashSet::new();
+    for (i, root) in roots.into_iter().enumerate() {
+        if i != 0 {
+            writeln!(out)?;
+        }
+
+        let pkg = &graph[root];
+        writeln!(out, "{}", package_line(pkg, &pkg.dep_info, pkg.dep_info.is_optional))?;
+        printed.insert(root);
+        write_children(out, graph, root, &mut String::new(), &mut printed)?;
+    }
+
+    Ok(())
+}
+
+fn write_children(
+    out: &mut impl Write,
+    graph: &DepGraph,
+    idx: NodeIndex<u16>,
+    prefix: &mut String,
+    printed: &mut HashSet<NodeIndex<u16>>,
+) -> io::Result<()> {
+    // There can be multiple edges to the same dependency, but it should only be listed once.
+    let mut children: Vec<(NodeIndex<u16>, DepInfo)> = Vec::new();
+    for edge in graph.edges_directed(idx, Direction::Outgoing) {
+        let info = edge.weight().info;
+        match children.iter_mut().find(|(child, _)| *child == edge.target()) {
+            Some((_, child_info)) => child_info.combine_incoming(info),
+            None => children.push((edge.target(), info)),
+        }
+    }
+    children.sort_by(|&(a, _), &(b, _)| cmp_packages(&graph[a], &graph[b]));
+
+    let num_children = children.len();
+    for (i, (child, info)) in children.into_iter().enumerate() {
+        let is_last = i + 1 == num_children;
+        let has_children = graph.neighbors_directed(child, Direction::Outgoing).next().is_some();
+        let is_repeated = !printed.insert(child) && has_children;
+
+        let branch = if is_last { "└── " } else { "├── " };
+        let line = package_line(&graph[child], &info, info.is_optional_direct);
+        let repeated_marker = if is_repeated { " (*)" } else { "" };
+        writeln!(out, "{prefix}{branch}{line}{repeated_marker}")?;
+
+        if !is_repeated {
+            let prefix_len = prefix.len();
+            prefix.push_str(if is_last { "    " } else { "│   " });
+            write_children(out, graph, child, prefix, printed)?;
+            prefix.truncate(prefix_len);
+        }
+    }
+
+    Ok(())
+}
+
+/// The package's name, version and source, tagged with the dependency kind and whether the
+/// dependency is optional or target-specific unless it is a regular normal dependency.
+fn package_line(pkg: &Package, info: &DepInfo, is_optional_direct: bool) -> String {
+    let mut line = match pkg.source {
+        PackageSource::Placeholder(_) => pkg.name.clone(),
+        _ => format!("{} v{}", pkg.name, pkg.version),
+    };
+
+    match &pkg.source {
+        PackageSource::CratesIo => {}
+        PackageSource::Registry(url) => line += &format!(" (registry {url})"),
+        PackageSource::Git(url) => line += &format!(" ({url})"),
+        PackageSource::Path => {
+            if let Some(path) = &pkg.path {
+                line += &format!(" ({path})");
+            }
+        }
+        PackageSource::Placeholder(_) => {}
+    }
+
+    let optional = if is_optional_direct {
+        Some("optional")
+    } else if info.is_optional {
+        Some("transitively optional")
+    } else {
+        None
+    };
+    if info.kind != DepKind::NORMAL || optional.is_some() || info.is_target_dep {
+        line += &format!(" [{}]", describe(info.kind, info.is_target_dep, optional));
+    }
+
+    line
+}
+
+fn cmp_packages(a: &Package, b: &Package) -> Ordering {
+    (&a.name, &a.version, &a.id).cmp(&(&b.name, &b.version, &b.id))
+}
@@ -4,7 +4,7 @@ use std::{
     rc::Rc,
 };
 
-use cargo_metadata::{Package as MetaPackage, semver::Version};
+use cargo_metadata::{Package as MetaPackage, Source, semver::Version};
 
 use crate::{
     dep_info::{DepInfo, DepKind},
@@ -13,11 +13,21 @@ use crate::{
 
 #[derive(Clone)]
 pub(crate) struct Package {
+    /// identifier that only depends on the package itself, not on the rest of the graph
+    pub id: String,
     pub name: String,
     pub version: Version,
+    pub source: PackageSource,
+    /// the package's directory, for packages from a local path
+    pub path: Option<String>,
     pub dep_info: DepInfo,
     pub is_ws_member: bool,
     pub is_proc_macro: bool,
+    /// the features that are enabled for this package
+    pub features: Vec<String>,
+    /// for workspace members when combining several workspaces, the index of the workspace (in
+    /// the order of --manifest-path) it belongs to
+    pub workspace: Option<usize>,
 
     pub name_uses: Option<Rc<Cell<u16>>>,
 }
@@ -30,24 +40,202 @@ impl Package {
             dep_info.kind = DepKind::BUILD;
         }
 
+        let source = PackageSource::new(pkg.source.as_ref());
+        let path = (source == PackageSource::Path)
+            .then(|| pkg.manifest_path.parent().map(|dir| dir.to_string()))
+            .flatten();
+        let id = package_id(&pkg.name, &pkg.version, &source, path.as_deref(), &pkg.id.repr);
+
         Self {
+            id,
             name: pkg.name.clone(),
             version: pkg.version.clone(),
+            source,
+            path,
             dep_info,
             is_ws_member,
             is_proc_macro,
+            features: Vec::new(),
+            workspace: None,
+            name_uses: None,
+        }
+    }
+
+    pub fn placeholder(count: usize, dep_info: DepInfo, pruned_pkg_id: &str) -> Self {
+        Self {
+            id: format!("{pruned_pkg_id} (pruned)"),
+            name: format!("+{count} crates"),
+            version: Version::new(0, 0, 0),
+            source: PackageSource::Placeholder(count),
+            path: None,
+            dep_info,
+            is_ws_member: false,
+            is_proc_macro: false,
+            features: Vec::new(),
+            workspace: None,
             name_uses: None,
         }
     }
 }
 
+#[cfg(test)]
+impl Package {
+    /// A package from crates.io, for building graphs in tests.
+    pub fn test(name: &str, is_ws_member: bool) -> Self {
+        Self {
+            id: format!("{name}@0.1.0"),
+            name: name.to_owned(),
+            version: Version::new(0, 1, 0),
+            source: PackageSource::CratesIo,
+            path: None,
+            dep_info: DepInfo::default(),
+            is_ws_member,
+            is_proc_macro: false,
+            features: Vec::new(),
+            workspace: None,
+            name_uses: Some(Rc::new(Cell::new(1))),
+        }
+    }
+}
+
+/// The ID of a package in the outputs. Packages from local paths are told apart by their
+/// directory, as several of them can have the same name and version (e.g. when combining
+/// workspaces), or by the cargo package ID if the directory isn't known.
+fn package_id(
+    name: &str,
+    version: &Version,
+    source: &PackageSource,
+    path: Option<&str>,
+    cargo_id: &str,
+) -> String {
+    match (source, path) {
+        (PackageSource::CratesIo, _) => format!("{name}@{version}"),
+        (PackageSource::Registry(url), _) => format!("{name}@{version} ({url})"),
+        (PackageSource::Git(url), _) => format!("{name}@{version} (git+{url})"),
+        (PackageSource::Path, Some(path)) => format!("{name}@{version} (path+{path})"),
+        (PackageSource::Path, None) => format!("{name}@{version} (path {cargo_id})"),
+        (PackageSource::Placeholder(_), _) => unreachable!("placeholders have their own IDs"),
+    }
+}
+
 impl Debug for Package {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name)?;
-        if self.name_uses.as_ref().unwrap().get() > 1 {
+        if !matches!(self.source, PackageSource::Placeholder(_))
+            && self.name_uses.as_ref().unwrap().get() > 1
+        {
             write!(f, " {}", self.version)?;
         }
 
         Ok(())
     }
 }
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum PackageSource {
+    CratesIo,
+    /// an alternate registry, with the URL of its index
+    Registry(String),
+    /// a git repository, with its URL (including the revision)
+    Git(String),
+    /// a local path, either inside or outside of the workspace
+    Path,
+    /// not an actual package, but a placeholder for the dependencies removed by --prune, with the
+    /// number of packages it replaces
+    Placeholder(usize),
+}
+
+impl PackageSource {
+    /// Values accepted by `--source-filter` and `--exclude-source`.
+    pub const SELECTORS: [&'static str; 5] =
+        ["crates-io", "alt-registry", "registry", "git", "path"];
+
+    fn new(source: Option<&Source>) -> Self {
+        let Some(source) = source else {
+            return Self::Path;
+        };
+
+        let repr = &source.repr;
+        if source.is_crates_io() || repr == "sparse+https://index.crates.io/" {
+            Self::CratesIo
+        } else if let Some(url) = repr.strip_prefix("git+") {
+            Self::Git(url.to_owned())
+        } else {
+            let url = repr.split_once('+').map_or(repr.as_str(), |(_, url)| url);
+            Self::Registry(url.to_owned())
+        }
+    }
+
+    /// Whether this source is matched by one of the `SELECTORS`.
+    pub fn matches(&self, selector: &str) -> bool {
+        match self {
+            Self::CratesIo => selector == "crates-io" || selector == "registry",
+            Self::Registry(_) => selector == "alt-registry" || selector == "registry",
+            Self::Git(_) => selector == "git",
+            Self::Path => selector == "path",
+            Self::Placeholder(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_package_ids_include_the_directory() {
+        let version = Version::new(0, 1, 0);
+        let a = package_id("util", &version, &PackageSource::Path, Some("/ws/a/util"), "");
+        let b = package_id("util", &version, &PackageSource::Path, Some("/ws/b/util"), "");
+        assert_eq!(a, "util@0.1.0 (path+/ws/a/util)");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn path_package_ids_without_directory_use_the_cargo_id() {
+        let version = Version::new(0, 1, 0);
+        let a = package_id("util", &version, &PackageSource::Path, None, "util 0.1.0 #1");
+        let b = package_id("util", &version, &PackageSource::Path, None, "util 0.1.0 #2");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn package_ids_include_the_source() {
+        let version = Version::new(1, 2, 3);
+        let git = PackageSource::Git("https://example.com/repo#abc".to_owned());
+        let registry = PackageSource::Registry("https://example.com/index".to_owned());
+        assert_eq!(package_id("foo", &version, &PackageSource::CratesIo, None, ""), "foo@1.2.3");
+        assert_eq!(
+            package_id("foo", &version, &git, None, ""),
+            "foo@1.2.3 (git+https://example.com/repo#abc)"
+        );
+        assert_eq!(
+            package_id("foo", &version, &registry, None, ""),
+            "foo@1.2.3 (https://example.com/index)"
+        );
+    }
+
+    fn source(repr: &str) -> PackageSource {
+        PackageSource::new(Some(&Source { repr: repr.to_owned() }))
+    }
+
+    #[test]
+    fn sources() {
+        assert_eq!(
+            source("registry+https://github.com/rust-lang/crates.io-index"),
+            PackageSource::CratesIo
+        );
+        assert_eq!(source("sparse+https://index.crates.io/"), PackageSource::CratesIo);
+        assert_eq!(
+            source("sparse+https://my-registry.example.com/index/"),
+            PackageSource::Registry("https://my-registry.example.com/index/".to_owned())
+        );
+        assert_eq!(
+            source("git+https://github.com/rust-lang/log?rev=0123abc#0123abcdef"),
+            PackageSource::Git(
+                "https://github.com/rust-lang/log?rev=0123abc#0123abcdef".to_owned()
+            )
+        );
+        assert_eq!(PackageSource::new(None), PackageSource::Path);
+    }
+}
@@ -0,0 +1,147 @@
+use std::{collections::HashMap, io, iter};
+
+use cargo_metadata::{Metadata, MetadataCommand, PackageId};
+
+use crate::{
+    cache::cached_metadata,
+    cli::Config,
+    graph::{
+        DepGraph, dedup_transitive_deps, get_dep_graph, merge_parallel_edges, merge_workspaces,
+        prune_deps, remove_deps, remove_irrelevant_deps, select_paths, update_dep_info,
+    },
+    import::{metadata_from_binary, metadata_from_lockfile, metadata_from_sbom},
+    output::{
+        write_output, write_output_file, write_sqlite_file, write_svg_file, write_table_files,
+    },
+    util::set_name_stats,
+};
+
+/// Gets the metadata from `cargo metadata` or one of the other inputs, along with the workspace
+/// each package belongs to if several workspaces are combined.
+pub(crate) fn load_metadata(
+    config: &Config,
+) -> anyhow::Result<(Metadata, HashMap<PackageId, usize>)> {
+    let (metadata, workspaces) = if let Some(path) = &config.from_sbom {
+        (metadata_from_sbom(path)?, HashMap::new())
+    } else if let Some(path) = &config.from_binary {
+        (metadata_from_binary(path)?, HashMap::new())
+    } else if let Some(path) = &config.lockfile {
+        (metadata_from_lockfile(path)?, HashMap::new())
+    } else if config.manifest_path.len() > 1 {
+        let metadata = config
+            .manifest_path
+            .iter()
+            .map(|path| cargo_metadata(config, Some(path)))
+            .collect::<anyhow::Result<_>>()?;
+        merge_workspaces(metadata)?
+    } else {
+        (cargo_metadata(config, config.manifest_path.first())?, HashMap::new())
+    };
+
+    Ok((metadata, workspaces))
+}
+
+/// Builds the dependency graph and applies all of the filters from the config.
+pub(crate) fn build_graph(
+    metadata: Metadata,
+    workspaces: &HashMap<PackageId, usize>,
+    config: &Config,
+) -> anyhow::Result<DepGraph> {
+    let mut graph = get_dep_graph(metadata, workspaces, config)?;
+    update_dep_info(&mut graph);
+    if !config.focus.is_empty() {
+        remove_irrelevant_deps(&mut graph, &config.focus);
+    }
+    if !config.hide.is_empty() {
+        remove_deps(&mut graph, &config.hide);
+    }
+    if !config.prune.is_empty() {
+        prune_deps(&mut graph, &config.prune);
+    }
+    if !config.path_from.is_empty() {
+        select_paths(
+            &mut graph,
+            &config.path_from,
+            &config.path_to,
+            config.max_paths,
+            config.highlight_paths,
+        )?;
+    }
+    if config.dedup_transitive_deps {
+        dedup_transitive_deps(&mut graph);
+    }
+    if config.merge_edges {
+        merge_parallel_edges(&mut graph);
+    }
+    set_name_stats(&mut graph);
+
+    Ok(graph)
+}
+
+/// Writes the graph to the destination selected in the config. Output formats that aren't written
+/// to files of their own go to `output`, or stdout if it is `None`.
+pub(crate) fn write_graph(
+    graph: &DepGraph,
+    config: &Config,
+    output: Option<&str>,
+) -> anyhow::Result<()> {
+    if let Some(path) = &config.sqlite {
+        return write_sqlite_file(path, graph);
+    }
+
+    match &config.svg {
+        Some(path) => write_svg_file(path, graph, config)?,
+        None if matches!(config.output_format.as_str(), "csv" | "tsv") => {
+            write_table_files(graph, config)?;
+        }
+        None => match output {
+            Some(path) => write_output_file(path, graph, config)?,
+            None => write_output(&mut io::stdout().lock(), graph, config)?,
+        },
+    }
+
+    Ok(())
+}
+
+fn cargo_metadata(config: &Config, manifest_path: Option<&String>) -> anyhow::Result<Metadata> {
+    if config.cache {
+        cached_metadata(config, manifest_path, || run_cargo_metadata(config, manifest_path))
+    } else {
+        run_cargo_metadata(config, manifest_path)
+    }
+}
+
+fn run_cargo_metadata(config: &Config, manifest_path: Option<&String>) -> anyhow::Result<Metadata> {
+    let mut cmd = MetadataCommand::new();
+
+    if let Some(path) = manifest_path {
+        cmd.manifest_path(path);
+    }
+
+    let mut other_options = Vec::new();
+    other_options.extend(config.features.iter().flat_map(|f| cli_args("--features", f)));
+    if config.all_features {
+        other_options.push("--all-features".into());
+    }
+    if config.no_default_features {
+        other_options.push("--no-default-features".into());
+    }
+    other_options
+        .extend(config.filter_platform.iter().flat_map(|p| cli_args("--filter-platform", p)));
+    if config.frozen {
+        other_options.push("--frozen".into());
+    }
+    if config.locked {
+        other_options.push("--locked".into());
+    }
+    if config.offline {
+        other_options.push("--offline".into());
+    }
+    other_options.extend(config.unstable_flags.iter().flat_map(|f| cli_args("-Z", f)));
+
+    Ok(cmd.other_options(other_options).exec()?)
+}
+
+fn cli_args(opt_name: &str, val: &str) -> impl Iterator<Item = String> {
+    iter::once(opt_name.into()).chain(iter::once(val.into()))
+}